@@ -1,40 +1,69 @@
 use std::sync::Arc;
+use std::time::Instant;
 
-use rand::random_range;
 use rust_raytracer::base::*;
 use rust_raytracer::materials::*;
 use rust_raytracer::objects::*;
 use rust_raytracer::render::*;
 
 static MAX_DEPTH: u32 = 25;
+static SCENE_SEED: u64 = 2025;
 static N_BALLS: i32 = 25;
 
 fn main() -> std::io::Result<()> {
     let renderer = Renderer {
         samples_range: (8, 1000),
-        max_depth: MAX_DEPTH,
+        integrator: PathTracer {
+            max_depth: MAX_DEPTH,
+            ..Default::default()
+        },
         time_sampler: Some(halton_sampler(2)),
-        tolerable_cv: 0.01,
+        tolerable_error: 0.01,
+        ..Renderer::new()
     };
 
-    let (world, camera) = bouncing_balls(N_BALLS, true);
     println!("Render Task #1: Multi-threaded, using BVH tree");
-    let file = get_output_file("bouncing_balls_mt_bvh")?;
-    let heatmap_file = get_output_file("bouncing_heatmap")?;
-    renderer.multi_threaded_render(&camera, &world, file, Some(heatmap_file), None)?;
+    let stats = render(&renderer, true, "bouncing_balls_mt_bvh")?;
+    println!("{stats}");
+
+    println!("Render Task #2: Multi-threaded, using a plain list");
+    let stats = render(&renderer, false, "bouncing_balls_mt_list")?;
+    println!("{stats}");
 
     Ok(())
 }
 
-fn get_output_file(name: &str) -> std::io::Result<std::fs::File> {
+// Renders the scene and saves the image, its sample count heatmap and its statistics
+fn render(renderer: &Renderer, bvh: bool, name: &str) -> std::io::Result<RenderStats> {
+    let start = Instant::now();
+    let (world, camera) = bouncing_balls(N_BALLS, bvh);
+    let build_time = start.elapsed();
+
+    let (film, mut stats) = renderer.multi_threaded_render(&camera, &world)?;
+    stats.build_time = build_time;
+
+    stats.write_time += renderer.write_image(&film, get_output_file(name, renderer.format)?)?;
+    let heatmap_file = get_output_file(&format!("{name}_heatmap"), renderer.format)?;
+    stats.write_time += renderer.write_aov(&film, Aov::SampleCount, heatmap_file)?;
+
+    let mut path = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    path.push("output");
+    path.push(format!("{name}_stats.json"));
+    stats.write_json(std::fs::File::create(path)?)?;
+    Ok(stats)
+}
+
+fn get_output_file(name: &str, format: ImageFormat) -> std::io::Result<std::fs::File> {
     let mut path = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
     path.push("output");
     std::fs::create_dir_all(&path)?;
-    path.push(format!("{name}.ppm"));
+    path.push(format!("{name}.{}", format.extension()));
     std::fs::File::create(path)
 }
 
 pub fn bouncing_balls(n: i32, bvh: bool) -> (World, Camera) {
+    // fixed seed so the scene layout is the same on every run
+    let mut rng = Rng::new(SCENE_SEED, 0);
     let mut geometry = HittableList::new();
 
     let ground_material: Arc<dyn Material> = Lambertian::new(Color::new(0.5, 0.5, 0.5));
@@ -46,23 +75,25 @@ pub fn bouncing_balls(n: i32, bvh: bool) -> (World, Camera) {
 
     for a in -n..n {
         for b in -n..n {
-            let choose_mat = random_range(0.0..1.0);
-            let choose_bounce = random_range(0.0..1.0);
+            let choose_mat = rng.random_range(0.0..1.0);
+            let choose_bounce = rng.random_range(0.0..1.0);
             let center = Point3::new(
-                a as f64 + 0.9 * random_range(0.0..1.0),
+                a as f64 + 0.9 * rng.random_range(0.0..1.0),
                 0.2,
-                b as f64 + 0.9 * random_range(0.0..1.0),
+                b as f64 + 0.9 * rng.random_range(0.0..1.0),
             );
-            let translation = Vec3::new(0.0, random_range(0.0..0.5), 0.0);
+            let height = rng.random_range(0.0..0.5);
 
             if (center - Point3::new(4.0, 0.2, 0.0)).length() > 0.9 {
                 let sphere_material: Arc<dyn Material> = match choose_mat {
                     x if x < 0.5 => {
-                        let albedo = Color::random(0.0..1.0) * Color::random(0.0..1.0);
+                        let albedo =
+                            Color::random(&mut rng, 0.0..1.0) * Color::random(&mut rng, 0.0..1.0);
                         Lambertian::new(albedo)
                     }
                     x if x < 0.8 => {
-                        let albedo = Color::random(0.0..1.0) * Color::random(0.0..1.0);
+                        let albedo =
+                            Color::random(&mut rng, 0.0..1.0) * Color::random(&mut rng, 0.0..1.0);
                         Metal::new(albedo)
                     }
                     _ => Dielectric::new(1.5),
@@ -71,11 +102,7 @@ pub fn bouncing_balls(n: i32, bvh: bool) -> (World, Camera) {
                 if choose_bounce > 0.5 {
                     geometry.add(sphere);
                 } else {
-                    geometry.add(Translating::new(
-                        sphere,
-                        Vec3::new(0.0, 0.0, 0.0),
-                        translation,
-                    ));
+                    geometry.add(Translating::along(sphere, bounce(height)));
                 }
             }
         }
@@ -113,3 +140,14 @@ pub fn bouncing_balls(n: i32, bvh: bool) -> (World, Camera) {
 
     (World::new(backdrop_color, geometry), cam)
 }
+
+// Up and back down once while the shutter is open
+// Hermite with these tangents traces the parabola of a thrown ball
+fn bounce(height: f64) -> Track<Vec3> {
+    let up = Vec3::new(0.0, 1.0, 0.0);
+    Track::new(0.0, Vec3::ZERO)
+        .with_tangent(up * (4.0 * height))
+        .then(Interpolation::Hermite, 0.5, up * height)
+        .then(Interpolation::Hermite, 1.0, Vec3::ZERO)
+        .with_tangent(up * (-4.0 * height))
+}
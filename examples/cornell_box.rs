@@ -10,25 +10,44 @@ static MAX_DEPTH: u32 = 50;
 fn main() -> std::io::Result<()> {
     let renderer = Renderer {
         samples_range: (64, 1000),
-        max_depth: MAX_DEPTH,
+        integrator: PathTracer {
+            max_depth: MAX_DEPTH,
+            ..Default::default()
+        },
         time_sampler: Some(halton_sampler(2)),
-        tolerable_cv: 0.01,
+        sampler: Arc::new(PaddedSobolSampler),
+        tolerable_error: 0.01,
+        aovs: vec![Aov::Albedo, Aov::Normal, Aov::Depth, Aov::SampleCount],
+        ..Renderer::new()
+    };
+    let progressive = Progressive {
+        samples_per_pass: 16,
+        ..Default::default()
     };
-    let file = get_output_file("cornell_box")?;
-    let heatmap_file = get_output_file("cornell_box_heatmap")?;
 
     let (world, camera) = cornell_box();
 
-    renderer.multi_threaded_render(&camera, &world, file, Some(heatmap_file), None)?;
+    // the image is rewritten after every pass, so it can be watched while it refines
+    let film = renderer.progressive_render(&camera, &world, progressive, |film, pass| {
+        println!(
+            "pass {}: {}-{} samples, noise {:.4}, {:.1?}",
+            pass.index, pass.min_samples, pass.max_samples, pass.noise, pass.elapsed
+        );
+        renderer.write_image(film, get_output_file("cornell_box", renderer.format)?)?;
+        Ok(())
+    })?;
+    renderer.write_aovs(&film, |aov| {
+        get_output_file(&format!("cornell_box_{}", aov.name()), renderer.format)
+    })?;
 
     Ok(())
 }
 
-fn get_output_file(name: &str) -> std::io::Result<std::fs::File> {
+fn get_output_file(name: &str, format: ImageFormat) -> std::io::Result<std::fs::File> {
     let mut path = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
     path.push("output");
     std::fs::create_dir_all(&path)?;
-    path.push(format!("{name}.ppm"));
+    path.push(format!("{name}.{}", format.extension()));
     std::fs::File::create(path)
 }
 
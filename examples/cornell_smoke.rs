@@ -7,27 +7,46 @@ use rust_raytracer::render::*;
 
 static MAX_DEPTH: u32 = 50;
 
+// `cargo run --example cornell_smoke -- x0 y0 x1 y1` only renders that rectangle of pixels
 fn main() -> std::io::Result<()> {
+    let bounds: Vec<u32> = std::env::args()
+        .skip(1)
+        .map(|arg| arg.parse().expect("crop bounds are pixel coordinates"))
+        .collect();
+    let crop = match bounds[..] {
+        [x0, y0, x1, y1] => Some(CropWindow::new(x0, y0, x1, y1)),
+        _ => None,
+    };
+
     let renderer = Renderer {
         samples_range: (128, 1000),
-        max_depth: MAX_DEPTH,
+        integrator: PathTracer {
+            max_depth: MAX_DEPTH,
+            ..Default::default()
+        },
         time_sampler: Some(halton_sampler(2)),
-        tolerable_cv: 0.01,
+        tolerable_error: 0.01,
+        denoiser: Denoiser::ATROUS,
+        crop,
+        ..Renderer::new()
     };
-    let file = get_output_file("cornell_smoke")?;
+    let file = get_output_file("cornell_smoke", renderer.format)?;
+    let denoised_file = get_output_file("cornell_smoke_denoised", renderer.format)?;
 
     let (world, camera) = cornell_smoke();
 
-    renderer.multi_threaded_render(&camera, &world, file, None, None)?;
+    let (film, _) = renderer.multi_threaded_render(&camera, &world)?;
+    renderer.write_image(&film, file)?;
+    renderer.write_image(&renderer.denoise(&film), denoised_file)?;
 
     Ok(())
 }
 
-fn get_output_file(name: &str) -> std::io::Result<std::fs::File> {
+fn get_output_file(name: &str, format: ImageFormat) -> std::io::Result<std::fs::File> {
     let mut path = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
     path.push("output");
     std::fs::create_dir_all(&path)?;
-    path.push(format!("{name}.ppm"));
+    path.push(format!("{name}.{}", format.extension()));
     std::fs::File::create(path)
 }
 
@@ -1,38 +1,52 @@
 use std::sync::Arc;
 
-use rand::random_range;
 use rust_raytracer::base::*;
 use rust_raytracer::materials::*;
 use rust_raytracer::objects::*;
 use rust_raytracer::render::*;
 
 static MAX_DEPTH: u32 = 50;
+static SCENE_SEED: u64 = 2025;
 
 fn main() -> std::io::Result<()> {
     let renderer = Renderer {
         samples_range: (8, 1000),
-        max_depth: MAX_DEPTH,
+        integrator: PathTracer {
+            max_depth: MAX_DEPTH,
+            ..Default::default()
+        },
         time_sampler: Some(halton_sampler(2)),
-        tolerable_cv: 0.01,
+        tolerable_error: 0.01,
+        filter: Filter::MITCHELL,
+        tile_order: TileOrder::Spiral,
+        // rerunning after an interruption resumes from the finished tiles
+        checkpoint: Some(Checkpoint::new(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/output/final_scene.checkpoint"
+        ))),
+        ..Renderer::new()
     };
-    let file = get_output_file("final_scene")?;
+    let file = get_output_file("final_scene", renderer.format)?;
 
     let (world, camera) = final_scene();
 
-    renderer.multi_threaded_render(&camera, &world, file, None, None)?;
+    let (film, _) = renderer.multi_threaded_render(&camera, &world)?;
+    renderer.write_image(&film, file)?;
 
     Ok(())
 }
 
-fn get_output_file(name: &str) -> std::io::Result<std::fs::File> {
+fn get_output_file(name: &str, format: ImageFormat) -> std::io::Result<std::fs::File> {
     let mut path = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
     path.push("output");
     std::fs::create_dir_all(&path)?;
-    path.push(format!("{name}.ppm"));
+    path.push(format!("{name}.{}", format.extension()));
     std::fs::File::create(path)
 }
 
 pub fn final_scene() -> (World, Camera) {
+    // fixed seed so the scene layout is the same on every run
+    let mut rng = Rng::new(SCENE_SEED, 0);
     let mut geometry = HittableList::new();
 
     let mut boxes1 = HittableList::new();
@@ -47,7 +61,7 @@ pub fn final_scene() -> (World, Camera) {
             let y0 = 0.0;
             let x1 = x0 + w;
 
-            let y1 = random_range(1.0..101.0);
+            let y1 = rng.random_range(1.0..101.0);
             let z1 = z0 + w;
 
             boxes1.add(Block::new(
@@ -121,9 +135,9 @@ pub fn final_scene() -> (World, Camera) {
 
     for _ in 0..ns {
         let random_point = Point3::new(
-            random_range(0.0..165.0),
-            random_range(0.0..165.0),
-            random_range(0.0..165.0),
+            rng.random_range(0.0..165.0),
+            rng.random_range(0.0..165.0),
+            rng.random_range(0.0..165.0),
         );
         boxes2.add(Sphere::new(random_point, 10.0, white_material.clone()));
     }
@@ -10,24 +10,29 @@ static MAX_DEPTH: u32 = 10;
 fn main() -> std::io::Result<()> {
     let renderer = Renderer {
         samples_range: (8, 1000),
-        max_depth: MAX_DEPTH,
+        integrator: PathTracer {
+            max_depth: MAX_DEPTH,
+            ..Default::default()
+        },
         time_sampler: Some(halton_sampler(2)),
-        tolerable_cv: 0.01,
+        tolerable_error: 0.01,
+        ..Renderer::new()
     };
-    let file = get_output_file("helix")?;
+    let file = get_output_file("helix", renderer.format)?;
 
     let (world, camera) = helix();
 
-    renderer.multi_threaded_render(&camera, &world, file, None, None)?;
+    let (film, _) = renderer.multi_threaded_render(&camera, &world)?;
+    renderer.write_image(&film, file)?;
 
     Ok(())
 }
 
-fn get_output_file(name: &str) -> std::io::Result<std::fs::File> {
+fn get_output_file(name: &str, format: ImageFormat) -> std::io::Result<std::fs::File> {
     let mut path = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
     path.push("output");
     std::fs::create_dir_all(&path)?;
-    path.push(format!("{name}.ppm"));
+    path.push(format!("{name}.{}", format.extension()));
     std::fs::File::create(path)
 }
 
@@ -90,7 +95,7 @@ pub fn helix() -> (World, Camera) {
     let helix_sphere = Translating::new(
         Rotating::new(
             Sphere::new(sphere_pos, radius, mat),
-            box_center.clone(),
+            box_center,
             Axis::Y,
             90.0,
             -90.0,
@@ -6,28 +6,39 @@ use rust_raytracer::objects::*;
 use rust_raytracer::render::*;
 
 static MAX_DEPTH: u32 = 10;
+// From one side to the other
+static SWING_SECONDS: f64 = 1.0;
 
+// Writes pendulum_0000 to pendulum_0047, there and back at 24 frames per second
 fn main() -> std::io::Result<()> {
     let renderer = Renderer {
         samples_range: (8, 1000),
-        max_depth: MAX_DEPTH,
+        integrator: PathTracer {
+            max_depth: MAX_DEPTH,
+            ..Default::default()
+        },
         time_sampler: Some(halton_sampler(2)),
-        tolerable_cv: 0.01,
+        tolerable_error: 0.01,
+        // the emissive sphere is far above 1.0
+        display: DisplayTransform::new(0.0, ToneMap::Agx),
+        ..Renderer::new()
     };
-    let file = get_output_file("pendulum")?;
+    let animation = Animation::lasting(24.0, 2.0 * SWING_SECONDS);
 
     let (world, camera) = pendulum();
 
-    renderer.multi_threaded_render(&camera, &world, file, None, None)?;
-
-    Ok(())
+    renderer.render_animation(&camera, &world, animation, |frame, film, _| {
+        let file = get_output_file(&format!("pendulum_{frame:04}"), renderer.format)?;
+        renderer.write_image(film, file)?;
+        Ok(())
+    })
 }
 
-fn get_output_file(name: &str) -> std::io::Result<std::fs::File> {
+fn get_output_file(name: &str, format: ImageFormat) -> std::io::Result<std::fs::File> {
     let mut path = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
     path.push("output");
     std::fs::create_dir_all(&path)?;
-    path.push(format!("{name}.ppm"));
+    path.push(format!("{name}.{}", format.extension()));
     std::fs::File::create(path)
 }
 
@@ -117,10 +128,12 @@ pub fn pendulum() -> (World, Camera) {
     pendulum.add(arm_block);
     pendulum.add(pendulum_sphere);
 
-    // Rotating pendulum
-    let start_angle = 15.0;
-    let end_angle = 30.0;
-    let rotating_pendulum = Rotating::new(pendulum, pivot_center, Axis::Z, start_angle, end_angle);
+    // Rotating pendulum, slowing down at both ends of the swing
+    let amplitude = 30.0;
+    let swing = Track::new(0.0, -amplitude)
+        .then(Interpolation::Hermite, SWING_SECONDS, amplitude)
+        .then(Interpolation::Hermite, 2.0 * SWING_SECONDS, -amplitude);
+    let rotating_pendulum = Rotating::along(pendulum, pivot_center, Axis::Z, swing);
 
     geometry.add(rotating_pendulum);
 
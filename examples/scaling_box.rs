@@ -10,24 +10,29 @@ static MAX_DEPTH: u32 = 5;
 fn main() -> std::io::Result<()> {
     let renderer = Renderer {
         samples_range: (8, 1000),
-        max_depth: MAX_DEPTH,
+        integrator: PathTracer {
+            max_depth: MAX_DEPTH,
+            ..Default::default()
+        },
         time_sampler: Some(halton_sampler(2)),
-        tolerable_cv: 0.01,
+        tolerable_error: 0.01,
+        ..Renderer::new()
     };
-    let file = get_output_file("scaling_box")?;
+    let file = get_output_file("scaling_box", renderer.format)?;
 
     let (world, camera) = cornell_box();
 
-    renderer.multi_threaded_render(&camera, &world, file, None, None)?;
+    let (film, _) = renderer.multi_threaded_render(&camera, &world)?;
+    renderer.write_image(&film, file)?;
 
     Ok(())
 }
 
-fn get_output_file(name: &str) -> std::io::Result<std::fs::File> {
+fn get_output_file(name: &str, format: ImageFormat) -> std::io::Result<std::fs::File> {
     let mut path = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
     path.push("output");
     std::fs::create_dir_all(&path)?;
-    path.push(format!("{name}.ppm"));
+    path.push(format!("{name}.{}", format.extension()));
     std::fs::File::create(path)
 }
 
@@ -79,9 +84,9 @@ pub fn cornell_box() -> (World, Camera) {
 
     let mat = Metal::with_fuzz(Vec3::new(0.8, 0.6, 0.7), 1.0);
     let sphere = Sphere::new(Vec3::new(0.0, 0.0, 0.0), 50.0, mat);
-    let transform = Mat3::new([[2.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]]);
-    let sphere = Transformed::new(sphere, transform).unwrap();
-    let sphere = Translated::new(sphere, Vec3::new(278.0, 278.0, 400.0));
+    let transform = Transform::scaling(Vec3::new(2.0, 1.0, 1.0))
+        .then(&Transform::translation(Vec3::new(278.0, 278.0, 400.0)));
+    let sphere = Transformed::new(sphere, transform);
 
     geometry.add(sphere);
 
@@ -8,24 +8,29 @@ static MAX_DEPTH: u32 = 15;
 fn main() -> std::io::Result<()> {
     let renderer = Renderer {
         samples_range: (32, 1000),
-        max_depth: MAX_DEPTH,
+        integrator: PathTracer {
+            max_depth: MAX_DEPTH,
+            ..Default::default()
+        },
         time_sampler: Some(halton_sampler(2)),
-        tolerable_cv: 0.01,
+        tolerable_error: 0.01,
+        ..Renderer::new()
     };
-    let file = get_output_file("transform_test")?;
+    let file = get_output_file("transform_test", renderer.format)?;
 
     let (world, camera) = traffic_light();
 
-    renderer.multi_threaded_render(&camera, &world, file, None, None)?;
+    let (film, _) = renderer.multi_threaded_render(&camera, &world)?;
+    renderer.write_image(&film, file)?;
 
     Ok(())
 }
 
-fn get_output_file(name: &str) -> std::io::Result<std::fs::File> {
+fn get_output_file(name: &str, format: ImageFormat) -> std::io::Result<std::fs::File> {
     let mut path = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
     path.push("output");
     std::fs::create_dir_all(&path)?;
-    path.push(format!("{name}.ppm"));
+    path.push(format!("{name}.{}", format.extension()));
     std::fs::File::create(path)
 }
 
@@ -82,11 +87,10 @@ pub fn traffic_light() -> (World, Camera) {
         Metal::new(Vec3::new(1.0, 1.0, 1.0)),
     );
     // let sphere = Scaling::new(sphere, Vec3::new(0.0, 0.0, 0.0), Vec3::new(3.0, 1.0, 1.0), Vec3::new(1.0, 3.0, 1.0));
-    let transform1 = Mat3::new([[3.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]]);
-
-    let transform2 = Mat3::new([[1.0, 0.0, 0.0], [0.0, 3.0, 0.0], [0.0, 0.0, 1.0]]);
-    let sphere = Transforming::new(sphere, transform1, transform2).unwrap();
-    let sphere = Translated::new(sphere, Vec3::new(0.0, 5.0, 0.0));
+    let lift = Transform::translation(Vec3::new(0.0, 5.0, 0.0));
+    let transform1 = Transform::scaling(Vec3::new(3.0, 1.0, 1.0)).then(&lift);
+    let transform2 = Transform::scaling(Vec3::new(1.0, 3.0, 1.0)).then(&lift);
+    let sphere = Transforming::new(sphere, transform1, transform2);
 
     geometry.add(sphere);
 
@@ -30,17 +30,17 @@ SCENE="${examples[$INDEX]}"
 echo "Running example: $SCENE"
 cargo run --release --example "$SCENE"
 
-# Open the <output>.ppm
+# Open the <output>.png (or .ppm)
 echo "Searching for rendered images..."
 
 if [ "$SCENE" = "bouncing_balls" ]; then
   FILES=(
-    "$OUTPUT_DIR/bouncing_balls_mt_bvh.ppm"
-    "$OUTPUT_DIR/bouncing_balls_mt.ppm"
-    "$OUTPUT_DIR/bouncing_balls_st.ppm"
+    "$OUTPUT_DIR/bouncing_balls_mt_bvh.png"
+    "$OUTPUT_DIR/bouncing_balls_mt.png"
+    "$OUTPUT_DIR/bouncing_balls_st.png"
   )
 else
-  FILES=($(ls "$OUTPUT_DIR/$SCENE"*.ppm 2>/dev/null))
+  FILES=($(ls "$OUTPUT_DIR/$SCENE"*.png "$OUTPUT_DIR/$SCENE"*.ppm 2>/dev/null))
 fi
 
 if [ ${#FILES[@]} -eq 0 ]; then
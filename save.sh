@@ -1,7 +1,7 @@
 OUTPUT_DIR="output"
 SAVE_DIR="saved"
 
-mapfile -t outputs < <(ls $OUTPUT_DIR | grep '\.ppm$' | sed 's/.ppm//')
+mapfile -t outputs < <(ls $OUTPUT_DIR | grep '\.png$' | sed 's/.png//')
 
 echo "Available Scene"
 for i in "${!outputs[@]}"; do
@@ -27,8 +27,8 @@ while :; do
     filename="${outputs[$index]}"
     case "$cmd" in 
       "-o")
-        echo "opening $filename.ppm"
-        xdg-open "$OUTPUT_DIR/$filename.ppm";;
+        echo "opening $filename.png"
+        xdg-open "$OUTPUT_DIR/$filename.png";;
       "-s")
         echo "name to save: ${outputs[$index]}_???.png"
         args=""
@@ -36,8 +36,8 @@ while :; do
         # string is not empty
         [ -n "$args" ] &&  args="_$args"
         echo "saving to $SAVE_DIR/${filename}$args.png"
-        # using ImageMagick package
-        magick "$OUTPUT_DIR/$filename.ppm" "$SAVE_DIR/${filename}$args.png";;
+        # renderer already writes PNG
+        cp "$OUTPUT_DIR/$filename.png" "$SAVE_DIR/${filename}$args.png";;
       *)
         echo "Invalid: command is either -o or -s";;
     esac
@@ -1,3 +1,5 @@
+use crate::base::{Rng, Vec3};
+
 #[derive(Clone, Copy)]
 pub enum Axis {
     X,
@@ -16,6 +18,18 @@ impl Axis {
         }
     }
 
+    pub fn direction(&self) -> Vec3 {
+        match self {
+            Self::X => Vec3::new(1.0, 0.0, 0.0),
+            Self::Y => Vec3::new(0.0, 1.0, 0.0),
+            Self::Z => Vec3::new(0.0, 0.0, 1.0),
+        }
+    }
+
+    pub fn random(rng: &mut Rng) -> Self {
+        Self::AXES[((rng.random_f64() * 3.0) as usize).min(2)]
+    }
+
     pub fn prev(&self) -> Self {
         match self {
             Self::X => Self::Z,
@@ -24,14 +38,3 @@ impl Axis {
         }
     }
 }
-
-impl rand_distr::Distribution<Axis> for rand_distr::StandardUniform {
-    fn sample<R: rand::Rng + ?Sized>(&self, rng: &mut R) -> Axis {
-        let axis = rng.random_range(0..3);
-        match axis {
-            0 => Axis::X,
-            1 => Axis::Y,
-            _ => Axis::Z,
-        }
-    }
-}
@@ -1,32 +1,34 @@
-use std::fs::File;
-use std::io::{BufWriter, Write};
+use std::io::Write;
 
 use crate::base::{Interval, Vec3};
 
 pub type Color = Vec3;
 
-fn linear_to_gamma(linear_component: f64) -> f64 {
-    if linear_component > 0.0 {
-        linear_component.sqrt()
+// sRGB OETF
+pub fn linear_to_srgb(linear_component: f64) -> f64 {
+    if linear_component <= 0.0031308 {
+        12.92 * linear_component.max(0.0)
     } else {
-        0.0
+        1.055 * linear_component.powf(1.0 / 2.4) - 0.055
     }
 }
 
-pub fn write_color(writer: &mut BufWriter<File>, pixel_color: Color) -> std::io::Result<()> {
-    let mut r = pixel_color.x;
-    let mut g = pixel_color.y;
-    let mut b = pixel_color.z;
-
-    r = linear_to_gamma(r);
-    g = linear_to_gamma(g);
-    b = linear_to_gamma(b);
+// Quantizes an already display-encoded color into 8-bit RGB
+pub fn color_to_bytes(pixel_color: Color) -> [u8; 3] {
+    let r = pixel_color.x;
+    let g = pixel_color.y;
+    let b = pixel_color.z;
 
     let intensity = Interval::new(0.000, 0.999);
-    let r_byte = (256.0 * intensity.clamp(r)) as i32;
-    let g_byte = (256.0 * intensity.clamp(g)) as i32;
-    let b_byte = (256.0 * intensity.clamp(b)) as i32;
+    let r_byte = (256.0 * intensity.clamp(r)) as u8;
+    let g_byte = (256.0 * intensity.clamp(g)) as u8;
+    let b_byte = (256.0 * intensity.clamp(b)) as u8;
+
+    [r_byte, g_byte, b_byte]
+}
 
+pub fn write_color(writer: &mut impl Write, pixel_color: Color) -> std::io::Result<()> {
+    let [r_byte, g_byte, b_byte] = color_to_bytes(pixel_color);
     writeln!(writer, "{} {} {}", r_byte, g_byte, b_byte)
 }
 
@@ -6,16 +6,18 @@ pub struct Mat3 {
 }
 
 impl Mat3 {
-    pub fn new(m: [[f64; 3]; 3]) -> Self {
+    pub const IDENTITY: Mat3 = Mat3::new([[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]]);
+
+    pub const fn new(m: [[f64; 3]; 3]) -> Self {
         Self { m }
     }
 
     pub fn transpose(&self) -> Mat3 {
         let mut m = [[0.0f64; 3]; 3];
 
-        for i in 0..3 {
-            for j in 0..3 {
-                m[j][i] = self.m[i][j];
+        for (i, row) in self.m.iter().enumerate() {
+            for (j, &value) in row.iter().enumerate() {
+                m[j][i] = value;
             }
         }
 
@@ -74,6 +76,13 @@ impl std::ops::Add for Mat3 {
     }
 }
 
+impl std::ops::Sub for Mat3 {
+    type Output = Mat3;
+    fn sub(self, rhs: Self) -> Self::Output {
+        self + rhs * -1.0
+    }
+}
+
 impl std::ops::Mul<f64> for Mat3 {
     type Output = Mat3;
     fn mul(self, rhs: f64) -> Self::Output {
@@ -92,10 +101,10 @@ impl std::ops::Mul for &Mat3 {
     fn mul(self, rhs: Self) -> Self::Output {
         let mut result = [[0.0; 3]; 3];
 
-        for i in 0..3 {
-            for j in 0..3 {
+        for (i, row) in result.iter_mut().enumerate() {
+            for (j, entry) in row.iter_mut().enumerate() {
                 for k in 0..3 {
-                    result[i][j] += self.m[i][k] * rhs.m[k][j];
+                    *entry += self.m[i][k] * rhs.m[k][j];
                 }
             }
         }
@@ -1,15 +1,27 @@
 mod axis;
 mod color;
 mod interval;
-mod lerp;
 mod mat3;
+mod onb;
+mod quat;
 mod ray;
+mod rng;
+mod sampler;
+mod sampling;
+mod track;
+mod transform;
 mod vec3;
 
 pub use axis::Axis;
 pub use color::*;
 pub use interval::Interval;
-pub use lerp::Lerp;
 pub use mat3::Mat3;
+pub use onb::Onb;
+pub use quat::Quat;
 pub use ray::Ray;
+pub use rng::{Rng, mix64};
+pub use sampler::Sampler;
+pub use sampling::*;
+pub use track::{Animatable, Interpolation, Key, Track};
+pub use transform::Transform;
 pub use vec3::{Point3, Vec3};
@@ -0,0 +1,42 @@
+use crate::base::Vec3;
+
+/*
+    Orthonormal basis
+
+    A right-handed frame (u, v, w) around a unit vector w, used to move
+    directions sampled around the z axis to around a normal or towards a light
+*/
+
+#[derive(Clone, Copy, Debug)]
+pub struct Onb {
+    pub u: Vec3,
+    pub v: Vec3,
+    pub w: Vec3,
+}
+
+impl Onb {
+    // `w` must be a unit vector
+    // Branchless construction of Duff et al. 2017, continuous except at w.z = -1
+    pub fn from_w(w: Vec3) -> Self {
+        let sign = 1.0_f64.copysign(w.z);
+        let a = -1.0 / (sign + w.z);
+        let b = w.x * w.y * a;
+        let u = Vec3::new(1.0 + sign * w.x * w.x * a, sign * b, -sign * w.x);
+        let v = Vec3::new(b, sign + w.y * w.y * a, -w.y);
+        Self { u, v, w }
+    }
+
+    // Local coordinates (z along w) to world space
+    pub fn to_world(&self, local: Vec3) -> Vec3 {
+        local.x * self.u + local.y * self.v + local.z * self.w
+    }
+
+    // World space to local coordinates
+    pub fn to_local(&self, direction: Vec3) -> Vec3 {
+        Vec3::new(
+            direction.dot(self.u),
+            direction.dot(self.v),
+            direction.dot(self.w),
+        )
+    }
+}
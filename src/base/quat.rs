@@ -0,0 +1,195 @@
+use crate::base::{Mat3, Vec3};
+
+/*
+    Unit quaternion, a rotation
+
+    w + xi + yj + zk rotates by 2 acos(w) about (x, y, z)
+*/
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Quat {
+    pub w: f64,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+impl Quat {
+    pub const IDENTITY: Quat = Quat::new(1.0, 0.0, 0.0, 0.0);
+
+    pub const fn new(w: f64, x: f64, y: f64, z: f64) -> Self {
+        Self { w, x, y, z }
+    }
+
+    // Counterclockwise about the axis when it points at the viewer
+    pub fn from_axis_angle(axis: Vec3, radians: f64) -> Self {
+        let axis = axis.unit_vector();
+        let (sin, cos) = (radians / 2.0).sin_cos();
+        Self::new(cos, axis.x * sin, axis.y * sin, axis.z * sin)
+    }
+
+    // Rotation part of a matrix that is a rotation, up to rounding
+    pub fn from_mat3(matrix: &Mat3) -> Self {
+        let m = &matrix.m;
+        let trace = m[0][0] + m[1][1] + m[2][2];
+        // largest of w, x, y, z first, so the division is well conditioned
+        let q = if trace > 0.0 {
+            let s = 2.0 * (trace + 1.0).sqrt();
+            Self::new(
+                0.25 * s,
+                (m[2][1] - m[1][2]) / s,
+                (m[0][2] - m[2][0]) / s,
+                (m[1][0] - m[0][1]) / s,
+            )
+        } else if m[0][0] > m[1][1] && m[0][0] > m[2][2] {
+            let s = 2.0 * (1.0 + m[0][0] - m[1][1] - m[2][2]).sqrt();
+            Self::new(
+                (m[2][1] - m[1][2]) / s,
+                0.25 * s,
+                (m[0][1] + m[1][0]) / s,
+                (m[0][2] + m[2][0]) / s,
+            )
+        } else if m[1][1] > m[2][2] {
+            let s = 2.0 * (1.0 + m[1][1] - m[0][0] - m[2][2]).sqrt();
+            Self::new(
+                (m[0][2] - m[2][0]) / s,
+                (m[0][1] + m[1][0]) / s,
+                0.25 * s,
+                (m[1][2] + m[2][1]) / s,
+            )
+        } else {
+            let s = 2.0 * (1.0 + m[2][2] - m[0][0] - m[1][1]).sqrt();
+            Self::new(
+                (m[1][0] - m[0][1]) / s,
+                (m[0][2] + m[2][0]) / s,
+                (m[1][2] + m[2][1]) / s,
+                0.25 * s,
+            )
+        };
+        q.normalized()
+    }
+
+    pub fn to_mat3(&self) -> Mat3 {
+        let Self { w, x, y, z } = *self;
+        Mat3::new([
+            [
+                1.0 - 2.0 * (y * y + z * z),
+                2.0 * (x * y - w * z),
+                2.0 * (x * z + w * y),
+            ],
+            [
+                2.0 * (x * y + w * z),
+                1.0 - 2.0 * (x * x + z * z),
+                2.0 * (y * z - w * x),
+            ],
+            [
+                2.0 * (x * z - w * y),
+                2.0 * (y * z + w * x),
+                1.0 - 2.0 * (x * x + y * y),
+            ],
+        ])
+    }
+
+    // Axis and angle in [0, 2 pi], any axis for the identity
+    pub fn axis_angle(&self) -> (Vec3, f64) {
+        let axis = Vec3::new(self.x, self.y, self.z);
+        let sin = axis.length();
+        if sin < 1e-12 {
+            return (Vec3::new(1.0, 0.0, 0.0), 0.0);
+        }
+        (axis / sin, 2.0 * sin.atan2(self.w))
+    }
+
+    pub fn rotate(&self, v: Vec3) -> Vec3 {
+        &self.to_mat3() * v
+    }
+
+    pub fn dot(&self, other: Quat) -> f64 {
+        self.w * other.w + self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
+    pub fn conjugate(&self) -> Quat {
+        Self::new(self.w, -self.x, -self.y, -self.z)
+    }
+
+    pub fn normalized(&self) -> Quat {
+        let length = self.dot(*self).sqrt();
+        Self::new(
+            self.w / length,
+            self.x / length,
+            self.y / length,
+            self.z / length,
+        )
+    }
+
+    // Same rotation, the other sign
+    pub fn negated(&self) -> Quat {
+        Self::new(-self.w, -self.x, -self.y, -self.z)
+    }
+
+    // Constant angular speed along the shorter way from a (t = 0) to b (t = 1)
+    pub fn slerp(a: Quat, b: Quat, t: f64) -> Quat {
+        let mut cos = a.dot(b);
+        let b = if cos < 0.0 {
+            cos = -cos;
+            b.negated()
+        } else {
+            b
+        };
+        // nearly the same rotation, where sin goes to zero
+        let (wa, wb) = if cos > 0.9995 {
+            (1.0 - t, t)
+        } else {
+            let theta = cos.acos();
+            let sin = theta.sin();
+            (((1.0 - t) * theta).sin() / sin, (t * theta).sin() / sin)
+        };
+        Self::new(
+            wa * a.w + wb * b.w,
+            wa * a.x + wb * b.x,
+            wa * a.y + wb * b.y,
+            wa * a.z + wb * b.z,
+        )
+        .normalized()
+    }
+}
+
+// a * b rotates by b, then by a
+impl std::ops::Mul for Quat {
+    type Output = Quat;
+    fn mul(self, rhs: Self) -> Self::Output {
+        Self::new(
+            self.w * rhs.w - self.x * rhs.x - self.y * rhs.y - self.z * rhs.z,
+            self.w * rhs.x + self.x * rhs.w + self.y * rhs.z - self.z * rhs.y,
+            self.w * rhs.y - self.x * rhs.z + self.y * rhs.w + self.z * rhs.x,
+            self.w * rhs.z + self.x * rhs.y - self.y * rhs.x + self.z * rhs.w,
+        )
+    }
+}
+
+// Componentwise, for blending keys, normalize the result to get a rotation
+impl std::ops::Add for Quat {
+    type Output = Quat;
+    fn add(self, rhs: Self) -> Self::Output {
+        Self::new(
+            self.w + rhs.w,
+            self.x + rhs.x,
+            self.y + rhs.y,
+            self.z + rhs.z,
+        )
+    }
+}
+
+impl std::ops::Sub for Quat {
+    type Output = Quat;
+    fn sub(self, rhs: Self) -> Self::Output {
+        self + rhs * -1.0
+    }
+}
+
+impl std::ops::Mul<f64> for Quat {
+    type Output = Quat;
+    fn mul(self, rhs: f64) -> Self::Output {
+        Self::new(self.w * rhs, self.x * rhs, self.y * rhs, self.z * rhs)
+    }
+}
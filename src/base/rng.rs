@@ -0,0 +1,106 @@
+use std::ops::Range;
+
+use crate::base::Sampler;
+
+/*
+    PCG32 (XSH-RR variant), see https://www.pcg-random.org
+
+    Every (pixel, sample) pair gets its own generator, so a render
+    only depends on the seed and not on thread scheduling.
+
+    A generator made by for_sampler hands out the dimensions of a Sampler
+    first, so every random decision along the path gets its values, and
+    only falls back to PCG32 once the sampler runs out of dimensions.
+*/
+
+#[derive(Clone)]
+pub struct Rng<'a> {
+    state: u64,
+    inc: u64,
+    dimensions: Option<Dimensions<'a>>,
+}
+
+// Where an rng made by for_sampler is in its sampler's dimensions
+#[derive(Clone)]
+struct Dimensions<'a> {
+    sampler: &'a dyn Sampler,
+    pixel: u64,
+    index: u32,
+    next: u32,
+}
+
+impl<'a> Rng<'a> {
+    const MULTIPLIER: u64 = 6364136223846793005;
+
+    pub fn new(seed: u64, stream: u64) -> Self {
+        let mut rng = Self {
+            state: 0,
+            inc: (stream << 1) | 1,
+            dimensions: None,
+        };
+        rng.next_u32();
+        rng.state = rng.state.wrapping_add(seed);
+        rng.next_u32();
+        rng
+    }
+
+    // Stream for sample `sample` of pixel `pixel`
+    pub fn for_sample(seed: u64, pixel: u64, sample: u64) -> Self {
+        Self::new(mix64(seed ^ mix64(sample)), pixel)
+    }
+
+    // Same stream as for_sample, behind the dimensions of `sampler`
+    pub fn for_sampler(seed: u64, pixel: u64, sample: u32, sampler: &'a dyn Sampler) -> Self {
+        let mut rng = Self::for_sample(seed, pixel, sample as u64);
+        rng.dimensions = Some(Dimensions {
+            sampler,
+            pixel: mix64(pixel ^ mix64(seed)),
+            index: sample,
+            next: 0,
+        });
+        rng
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        let old = self.state;
+        self.state = old.wrapping_mul(Self::MULTIPLIER).wrapping_add(self.inc);
+        let xorshifted = (((old >> 18) ^ old) >> 27) as u32;
+        let rot = (old >> 59) as u32;
+        xorshifted.rotate_right(rot)
+    }
+
+    // Uniform in [0, 1)
+    pub fn random_f64(&mut self) -> f64 {
+        if let Some(d) = &mut self.dimensions
+            && d.next < d.sampler.dimensions()
+        {
+            d.next += 1;
+            return d.sampler.sample_1d(d.pixel, d.index, d.next - 1);
+        }
+        let bits = ((self.next_u32() as u64) << 21) ^ (self.next_u32() as u64);
+        (bits & ((1 << 53) - 1)) as f64 / (1u64 << 53) as f64
+    }
+
+    // Two uniform numbers, the input of the warping functions
+    pub fn random_2d(&mut self) -> [f64; 2] {
+        if let Some(d) = &mut self.dimensions
+            && d.next.saturating_add(1) < d.sampler.dimensions()
+        {
+            d.next += 2;
+            return d.sampler.sample_2d(d.pixel, d.index, d.next - 2);
+        }
+        [self.random_f64(), self.random_f64()]
+    }
+
+    pub fn random_range(&mut self, range: Range<f64>) -> f64 {
+        range.start + (range.end - range.start) * self.random_f64()
+    }
+}
+
+// SplitMix64 finalizer, spreads nearby integers over the whole u64 range
+pub fn mix64(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    x ^ (x >> 31)
+}
@@ -0,0 +1,31 @@
+/*
+    Source of the sample values of a render
+
+    Each sample of a pixel asks for a series of numbers in [0, 1): time,
+    position in the pixel, lens position, then a few per bounce. Those are
+    its dimensions, and a sampler decides the value of every one of them.
+    Spreading the values of one dimension evenly over the samples of a
+    pixel (stratified, low-discrepancy) lowers the noise at the same count.
+
+    Samplers are random access: the value only depends on its arguments,
+    so samples can be taken in any order, on any thread.
+*/
+
+// Debug tells checkpoints made with different samplers apart
+pub trait Sampler: Send + Sync + std::fmt::Debug {
+    // Dimensions it provides, the rng supplies the ones after
+    fn dimensions(&self) -> u32 {
+        u32::MAX
+    }
+
+    // `pixel` identifies the pixel and the render seed, `index` the sample of the pixel
+    fn sample_1d(&self, pixel: u64, index: u32, dimension: u32) -> f64;
+
+    // Dimensions `dimension` and `dimension + 1`, spread over the square together
+    fn sample_2d(&self, pixel: u64, index: u32, dimension: u32) -> [f64; 2] {
+        [
+            self.sample_1d(pixel, index, dimension),
+            self.sample_1d(pixel, index, dimension + 1),
+        ]
+    }
+}
@@ -0,0 +1,84 @@
+use crate::base::Vec3;
+use crate::prelude::PI;
+
+/*
+    Warping functions
+
+    Each turns a uniform sample u in [0, 1)^2 into another distribution and
+    has a matching *_pdf giving its density. Directions come out in a local
+    frame around +z, Onb::to_world moves them around a normal.
+    Stratified or low-discrepancy u stay well spread after the warp.
+*/
+
+// Shirley-Chiu concentric map onto the unit disk, uniform by area
+// Less distortion than the polar map, so strata keep their shape
+pub fn sample_concentric_disk(u: [f64; 2]) -> (f64, f64) {
+    let a = 2.0 * u[0] - 1.0;
+    let b = 2.0 * u[1] - 1.0;
+    if a == 0.0 && b == 0.0 {
+        return (0.0, 0.0);
+    }
+    let (r, theta) = if a.abs() > b.abs() {
+        (a, PI / 4.0 * (b / a))
+    } else {
+        (b, PI / 2.0 - PI / 4.0 * (a / b))
+    };
+    (r * theta.cos(), r * theta.sin())
+}
+
+// Per unit area
+pub fn concentric_disk_pdf() -> f64 {
+    1.0 / PI
+}
+
+// Density proportional to cos(theta), by lifting a disk sample onto the hemisphere (Malley's method)
+pub fn sample_cosine_hemisphere(u: [f64; 2]) -> Vec3 {
+    let (x, y) = sample_concentric_disk(u);
+    let z = (1.0 - x * x - y * y).max(0.0).sqrt();
+    Vec3::new(x, y, z)
+}
+
+// Per solid angle, 0 below the horizon
+pub fn cosine_hemisphere_pdf(cos_theta: f64) -> f64 {
+    cos_theta.max(0.0) / PI
+}
+
+// Uniform over the directions within the cone of half angle acos(cos_max)
+pub fn sample_uniform_cone(u: [f64; 2], cos_max: f64) -> Vec3 {
+    let cos_theta = 1.0 + u[0] * (cos_max - 1.0);
+    let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+    let phi = 2.0 * PI * u[1];
+    Vec3::new(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta)
+}
+
+// Per solid angle, for directions inside the cone
+pub fn uniform_cone_pdf(cos_max: f64) -> f64 {
+    1.0 / (2.0 * PI * (1.0 - cos_max))
+}
+
+// Barycentric coordinates of a point uniform over any triangle
+pub fn sample_uniform_triangle(u: [f64; 2]) -> [f64; 3] {
+    let su0 = u[0].sqrt();
+    let b0 = 1.0 - su0;
+    let b1 = u[1] * su0;
+    [b0, b1, 1.0 - b0 - b1]
+}
+
+// Per unit area
+pub fn uniform_triangle_pdf(area: f64) -> f64 {
+    1.0 / area
+}
+
+// Uniform z in [-1, 1] and uniform angle around z
+// By Archimedes' hat-box theorem the point is uniform on the sphere
+pub fn sample_uniform_sphere(u: [f64; 2]) -> Vec3 {
+    let z = 1.0 - 2.0 * u[0];
+    let r = (1.0 - z * z).max(0.0).sqrt();
+    let phi = 2.0 * PI * u[1];
+    Vec3::new(r * phi.cos(), r * phi.sin(), z)
+}
+
+// Per solid angle
+pub fn uniform_sphere_pdf() -> f64 {
+    1.0 / (4.0 * PI)
+}
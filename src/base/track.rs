@@ -0,0 +1,477 @@
+use std::f64::consts::FRAC_PI_2;
+use std::ops::{Add, Mul, Sub};
+
+use crate::base::{Interval, Mat3, Quat, Vec3};
+
+/*
+    This file contains
+        1. Animatable (values a track can move between)
+        2. Interpolation (how a track gets from one key to the next)
+        3. Track (keyframes on the timeline)
+*/
+
+pub trait Animatable:
+    Copy + Add<Output = Self> + Sub<Output = Self> + Mul<f64, Output = Self>
+{
+    // Constant angular speed from a to b, a plain lerp for values that do not turn
+    fn slerp(a: Self, b: Self, t: f64) -> Self {
+        a * (1.0 - t) + b * t
+    }
+
+    // Values whose convex hull holds slerp(a, b, t) for every t in [0, 1]
+    fn slerp_hull(a: Self, b: Self) -> Vec<Self> {
+        vec![a, b]
+    }
+}
+
+impl Animatable for f64 {}
+
+// Offsets swing around the origin, their length changing linearly
+impl Animatable for Vec3 {
+    fn slerp(a: Self, b: Self, t: f64) -> Self {
+        match Swing::between(a, b) {
+            Some(swing) => swing.at(t),
+            None => a * (1.0 - t) + b * t,
+        }
+    }
+
+    fn slerp_hull(a: Self, b: Self) -> Vec<Self> {
+        let Some(swing) = Swing::between(a, b) else {
+            return vec![a, b];
+        };
+        let (u, v) = (swing.from * swing.radii.0, swing.from * swing.radii.1);
+        let (w, x) = (swing.side * swing.radii.0, swing.side * swing.radii.1);
+        arc_hull(swing.angle)
+            .into_iter()
+            .flat_map(|(cos, sin)| [u * cos + w * sin, v * cos + x * sin])
+            .collect()
+    }
+}
+
+// Keys have to be rotations for slerp, anything else is blended linearly
+impl Animatable for Mat3 {
+    fn slerp(a: Self, b: Self, t: f64) -> Self {
+        Quat::slerp(Quat::from_mat3(&a), Quat::from_mat3(&b), t).to_mat3()
+    }
+
+    /*
+        Between two rotations the matrix is R0 (I + sin K + (1 - cos) K^2),
+        K the cross product with the axis of the turn from a to b. That is
+        affine in (cos, sin), so the hull of the arc from 0 to the angle
+        gives the hull of the matrices.
+    */
+    fn slerp_hull(a: Self, b: Self) -> Vec<Self> {
+        let (q0, q1) = (Quat::from_mat3(&a), Quat::from_mat3(&b));
+        let mut turn = q0.conjugate() * q1;
+        if turn.w < 0.0 {
+            turn = turn.negated();
+        }
+        let (axis, angle) = turn.axis_angle();
+        let k = Mat3::new([
+            [0.0, -axis.z, axis.y],
+            [axis.z, 0.0, -axis.x],
+            [-axis.y, axis.x, 0.0],
+        ]);
+        let k2 = &k * &k;
+        arc_hull(angle)
+            .into_iter()
+            .map(|(cos, sin)| &a * &(Mat3::IDENTITY + k2 * (1.0 - cos) + k * sin))
+            .collect()
+    }
+}
+
+// Slerp stays in the plane of the two quaternions, on the unit circle there
+impl Animatable for Quat {
+    fn slerp(a: Self, b: Self, t: f64) -> Self {
+        Quat::slerp(a, b, t)
+    }
+
+    fn slerp_hull(a: Self, b: Self) -> Vec<Self> {
+        let (a, b) = (a.normalized(), b.normalized());
+        let b = if a.dot(b) < 0.0 { b.negated() } else { b };
+        let cos = a.dot(b).min(1.0);
+        let side = b - a * cos;
+        let length = side.dot(side).sqrt();
+        if length < 1e-9 {
+            return vec![a, b];
+        }
+        let side = side * (1.0 / length);
+        arc_hull(cos.acos())
+            .into_iter()
+            .map(|(cos, sin)| a * cos + side * sin)
+            .collect()
+    }
+}
+
+// Unit directions and lengths of two vectors, None when one of them is zero
+// or they point the same way
+struct Swing {
+    from: Vec3,
+    // unit, perpendicular to from, towards the other vector
+    side: Vec3,
+    angle: f64,
+    radii: (f64, f64),
+}
+
+impl Swing {
+    fn between(a: Vec3, b: Vec3) -> Option<Self> {
+        let radii = (a.length(), b.length());
+        if radii.0 < 1e-12 || radii.1 < 1e-12 {
+            return None;
+        }
+        let (from, to) = (a / radii.0, b / radii.1);
+        let angle = from.dot(to).clamp(-1.0, 1.0).acos();
+        if angle < 1e-9 {
+            return None;
+        }
+        let side = to - from * from.dot(to);
+        let side = if side.length() > 1e-9 {
+            side.unit_vector()
+        } else {
+            // opposite vectors, any way around will do
+            let helper = if from.x.abs() < 0.9 {
+                Vec3::new(1.0, 0.0, 0.0)
+            } else {
+                Vec3::new(0.0, 1.0, 0.0)
+            };
+            from.cross(helper).unit_vector()
+        };
+        Some(Self {
+            from,
+            side,
+            angle,
+            radii,
+        })
+    }
+
+    fn at(&self, t: f64) -> Vec3 {
+        let (sin, cos) = (self.angle * t).sin_cos();
+        let radius = self.radii.0 * (1.0 - t) + self.radii.1 * t;
+        (self.from * cos + self.side * sin) * radius
+    }
+}
+
+// Points (cos, sin) whose hull holds the unit circle from angle 0 to `angle`
+// Pieces of at most a quarter turn, each with the corner where the tangents
+// at its ends meet
+fn arc_hull(angle: f64) -> Vec<(f64, f64)> {
+    let pieces = (angle.abs() / FRAC_PI_2).ceil().max(1.0) as usize;
+    let step = angle / pieces as f64;
+    let mut points = vec![(1.0, 0.0)];
+    for piece in 0..pieces {
+        let middle = (piece as f64 + 0.5) * step;
+        let reach = 1.0 / (step / 2.0).cos();
+        points.push((middle.cos() * reach, middle.sin() * reach));
+        let end = (piece + 1) as f64 * step;
+        points.push((end.cos(), end.sin()));
+    }
+    points
+}
+
+// How a track goes from a key to the next one
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Interpolation {
+    // Holds the value until the next key
+    Step,
+    Linear,
+    // Cubic through both keys with their tangents, zero where a key has none,
+    // which eases in and out
+    Hermite,
+    // Cubic through both keys with tangents from the neighbouring keys
+    CatmullRom,
+    // Eases time by the curve from (0, 0) to (1, 1) with control points
+    // (x1, y1) and (x2, y2), like CSS cubic-bezier
+    Bezier(f64, f64, f64, f64),
+    // Constant angular speed, see Animatable::slerp
+    Slerp,
+}
+
+impl Interpolation {
+    pub const EASE_IN_OUT: Interpolation = Interpolation::Bezier(0.42, 0.0, 0.58, 1.0);
+}
+
+#[derive(Clone, Debug)]
+pub struct Key<T> {
+    pub time: f64,
+    pub value: T,
+    // Change per unit of time, used by Hermite
+    pub tangent: Option<T>,
+    // Towards the next key
+    pub interpolation: Interpolation,
+}
+
+// Value over absolute time, given at keys in time order
+// Before the first key and after the last one the value holds still
+#[derive(Clone, Debug)]
+pub struct Track<T> {
+    keys: Vec<Key<T>>,
+}
+
+impl<T: Copy> Track<T> {
+    pub fn new(time: f64, value: T) -> Self {
+        Self {
+            keys: vec![Key {
+                time,
+                value,
+                tangent: None,
+                interpolation: Interpolation::Linear,
+            }],
+        }
+    }
+
+    pub fn constant(value: T) -> Self {
+        Self::new(0.0, value)
+    }
+
+    // From begin at time 0 to end at time 1
+    pub fn linear(begin: T, end: T) -> Self {
+        Self::new(0.0, begin).then(Interpolation::Linear, 1.0, end)
+    }
+
+    // Adds a key, reached from the last one by `interpolation`
+    pub fn then(mut self, interpolation: Interpolation, time: f64, value: T) -> Self {
+        let last = self.keys.last_mut().unwrap();
+        assert!(time > last.time, "keys must come in time order");
+        last.interpolation = interpolation;
+        self.keys.push(Key {
+            time,
+            value,
+            tangent: None,
+            interpolation: Interpolation::Linear,
+        });
+        self
+    }
+
+    // Sets the tangent of the last key
+    pub fn with_tangent(mut self, tangent: T) -> Self {
+        self.keys.last_mut().unwrap().tangent = Some(tangent);
+        self
+    }
+
+    pub fn keys(&self) -> &[Key<T>] {
+        &self.keys
+    }
+
+    // Times of the first and the last key
+    pub fn times(&self) -> Interval {
+        Interval::new(self.keys[0].time, self.keys[self.keys.len() - 1].time)
+    }
+
+    // Same keys with the values passed through a linear map, e.g. degrees to radians
+    pub fn map<U: Copy>(&self, f: impl Fn(T) -> U) -> Track<U> {
+        Track {
+            keys: self
+                .keys
+                .iter()
+                .map(|key| Key {
+                    time: key.time,
+                    value: f(key.value),
+                    tangent: key.tangent.map(&f),
+                    interpolation: key.interpolation,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl<T: Animatable> Track<T> {
+    // Same motion stretched to happen during `times`
+    pub fn during(mut self, times: Interval) -> Self {
+        let old = self.times();
+        let span = old.max - old.min;
+        let scale = if span > 0.0 {
+            (times.max - times.min) / span
+        } else {
+            0.0
+        };
+        for key in &mut self.keys {
+            key.time = times.min + (key.time - old.min) * scale;
+            // the same change takes a different time
+            if scale > 0.0 {
+                key.tangent = key.tangent.map(|tangent| tangent * (1.0 / scale));
+            }
+        }
+        self
+    }
+
+    pub fn at(&self, time: f64) -> T {
+        let keys = &self.keys;
+        let last = keys.len() - 1;
+        if time <= keys[0].time {
+            return keys[0].value;
+        }
+        if time >= keys[last].time {
+            return keys[last].value;
+        }
+        let i = keys.partition_point(|key| key.time <= time) - 1;
+        let (k0, k1) = (&keys[i], &keys[i + 1]);
+        let dt = k1.time - k0.time;
+        let s = (time - k0.time) / dt;
+        let lerp = |t: f64| k0.value * (1.0 - t) + k1.value * t;
+
+        match k0.interpolation {
+            Interpolation::Step => k0.value,
+            Interpolation::Linear => lerp(s),
+            Interpolation::Hermite | Interpolation::CatmullRom => {
+                let (m0, m1) = self.tangents(i);
+                let s2 = s * s;
+                let s3 = s2 * s;
+                k0.value * (2.0 * s3 - 3.0 * s2 + 1.0)
+                    + m0 * ((s3 - 2.0 * s2 + s) * dt)
+                    + k1.value * (3.0 * s2 - 2.0 * s3)
+                    + m1 * ((s3 - s2) * dt)
+            }
+            Interpolation::Bezier(x1, y1, x2, y2) => lerp(bezier_ease(s, x1, y1, x2, y2)),
+            Interpolation::Slerp => T::slerp(k0.value, k1.value, s),
+        }
+    }
+
+    /*
+        Values whose convex hull holds every value of the track
+
+        Cubic segments are Bézier curves in disguise, the hull of their
+        control points holds them. Bounding boxes of moving objects are
+        built from these.
+    */
+    pub fn hull(&self) -> Vec<T> {
+        let keys = &self.keys;
+        let mut values = vec![keys[0].value];
+        for i in 0..keys.len() - 1 {
+            let (k0, k1) = (&keys[i], &keys[i + 1]);
+            let (p0, p1) = (k0.value, k1.value);
+            match k0.interpolation {
+                Interpolation::Step | Interpolation::Linear => {}
+                Interpolation::Hermite | Interpolation::CatmullRom => {
+                    let (m0, m1) = self.tangents(i);
+                    let third = (k1.time - k0.time) / 3.0;
+                    values.push(p0 + m0 * third);
+                    values.push(p1 - m1 * third);
+                }
+                Interpolation::Bezier(_, y1, _, y2) => {
+                    values.push(p0 * (1.0 - y1) + p1 * y1);
+                    values.push(p0 * (1.0 - y2) + p1 * y2);
+                }
+                Interpolation::Slerp => values.extend(T::slerp_hull(p0, p1)),
+            }
+            values.push(p1);
+        }
+        values
+    }
+
+    // Tangents at both ends of segment i
+    fn tangents(&self, i: usize) -> (T, T) {
+        let keys = &self.keys;
+        match keys[i].interpolation {
+            Interpolation::CatmullRom => (self.catmull_rom(i), self.catmull_rom(i + 1)),
+            _ => {
+                let zero = keys[i].value * 0.0;
+                (
+                    keys[i].tangent.unwrap_or(zero),
+                    keys[i + 1].tangent.unwrap_or(zero),
+                )
+            }
+        }
+    }
+
+    // Slope between the neighbours of key i, one-sided at the ends
+    fn catmull_rom(&self, i: usize) -> T {
+        let keys = &self.keys;
+        let before = &keys[i.saturating_sub(1)];
+        let after = &keys[(i + 1).min(keys.len() - 1)];
+        (after.value - before.value) * (1.0 / (after.time - before.time))
+    }
+}
+
+// y of the cubic Bézier easing curve where its x is `x`
+fn bezier_ease(x: f64, x1: f64, y1: f64, x2: f64, y2: f64) -> f64 {
+    let (x1, x2) = (x1.clamp(0.0, 1.0), x2.clamp(0.0, 1.0));
+    let curve = |s: f64, p1: f64, p2: f64| {
+        let r = 1.0 - s;
+        3.0 * r * r * s * p1 + 3.0 * r * s * s * p2 + s * s * s
+    };
+    // x grows with s, so bisection always finds it
+    let (mut low, mut high) = (0.0, 1.0);
+    for _ in 0..40 {
+        let middle = 0.5 * (low + high);
+        if curve(middle, x1, x2) < x {
+            low = middle;
+        } else {
+            high = middle;
+        }
+    }
+    curve(0.5 * (low + high), y1, y2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Whether every value of the track over its keys lies in the box around the hull
+    // `components` flattens a value into numbers
+    fn stays_in_hull<T: Animatable, const N: usize>(
+        track: &Track<T>,
+        components: impl Fn(T) -> [f64; N],
+    ) -> bool {
+        let hull: Vec<[f64; N]> = track.hull().into_iter().map(&components).collect();
+        let times = track.times();
+        (0..=1000).all(|step| {
+            let time = times.min + (times.max - times.min) * step as f64 / 1000.0;
+            let value = components(track.at(time));
+            (0..N).all(|c| {
+                let low = hull.iter().map(|v| v[c]).fold(f64::INFINITY, f64::min);
+                let high = hull.iter().map(|v| v[c]).fold(f64::NEG_INFINITY, f64::max);
+                (low - 1e-9..=high + 1e-9).contains(&value[c])
+            })
+        })
+    }
+
+    fn vec3(v: Vec3) -> [f64; 3] {
+        [v.x, v.y, v.z]
+    }
+
+    #[test]
+    fn hull_holds_every_interpolation() {
+        let tracks = [
+            Interpolation::Step,
+            Interpolation::Linear,
+            Interpolation::Hermite,
+            Interpolation::CatmullRom,
+            // overshoots both keys
+            Interpolation::Bezier(0.3, -0.6, 0.7, 1.6),
+            Interpolation::Slerp,
+        ]
+        .map(|interpolation| {
+            Track::new(0.0, Vec3::new(1.0, 0.0, 0.0))
+                .with_tangent(Vec3::new(0.0, 5.0, -3.0))
+                .then(interpolation, 1.0, Vec3::new(-1.0, 2.0, 0.5))
+                .with_tangent(Vec3::new(4.0, 0.0, 2.0))
+                .then(interpolation, 2.5, Vec3::new(0.0, -1.0, 3.0))
+                .then(interpolation, 3.0, Vec3::new(2.0, 1.0, 1.0))
+        });
+        for track in &tracks {
+            assert!(
+                stays_in_hull(track, vec3),
+                "{:?}",
+                track.keys()[0].interpolation
+            );
+        }
+        let scalar = Track::new(0.0, 0.0)
+            .with_tangent(10.0)
+            .then(Interpolation::Hermite, 1.0, 1.0)
+            .with_tangent(10.0);
+        assert!(stays_in_hull(&scalar, |x| [x]));
+    }
+
+    #[test]
+    fn hull_holds_turns() {
+        let a = Quat::from_axis_angle(Vec3::new(0.0, 1.0, 0.0), 0.3);
+        let b = Quat::from_axis_angle(Vec3::new(1.0, 1.0, 0.0).unit_vector(), 2.8);
+        let quats = Track::new(0.0, a).then(Interpolation::Slerp, 1.0, b);
+        assert!(stays_in_hull(&quats, |q| [q.w, q.x, q.y, q.z]));
+
+        let matrices = quats.map(|q| q.to_mat3());
+        assert!(stays_in_hull(&matrices, |m| -> [f64; 9] {
+            m.m.concat().try_into().unwrap()
+        }));
+    }
+}
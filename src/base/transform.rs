@@ -0,0 +1,314 @@
+use crate::base::{Mat3, Point3, Quat, Ray, Vec3};
+
+/*
+    Affine transform as a 4x4 matrix, with its inverse kept alongside
+
+    Points are columns (x, y, z, 1), vectors (x, y, z, 0). Every way of
+    building one knows its inverse, so hits never invert per ray.
+*/
+
+type Mat4 = [[f64; 4]; 4];
+
+const IDENTITY4: Mat4 = [
+    [1.0, 0.0, 0.0, 0.0],
+    [0.0, 1.0, 0.0, 0.0],
+    [0.0, 0.0, 1.0, 0.0],
+    [0.0, 0.0, 0.0, 1.0],
+];
+
+#[derive(Clone, Copy, Debug)]
+pub struct Transform {
+    m: Mat4,
+    inv: Mat4,
+}
+
+impl Transform {
+    pub const IDENTITY: Transform = Transform {
+        m: IDENTITY4,
+        inv: IDENTITY4,
+    };
+
+    // Linear part then translation, None when the linear part is singular
+    pub fn from_parts(linear: Mat3, translation: Vec3) -> Option<Self> {
+        let inverse = linear.inverse()?;
+        Some(Self {
+            m: affine(&linear, translation),
+            inv: affine(&inverse, -(&inverse * translation)),
+        })
+    }
+
+    pub fn from_mat3(linear: Mat3) -> Option<Self> {
+        Self::from_parts(linear, Vec3::ZERO)
+    }
+
+    // The last row has to be (0, 0, 0, 1)
+    pub fn from_matrix(m: [[f64; 4]; 4]) -> Option<Self> {
+        if m[3] != [0.0, 0.0, 0.0, 1.0] {
+            return None;
+        }
+        Self::from_parts(linear_part(&m), Vec3::new(m[0][3], m[1][3], m[2][3]))
+    }
+
+    pub fn translation(offset: Vec3) -> Self {
+        Self {
+            m: affine(&Mat3::IDENTITY, offset),
+            inv: affine(&Mat3::IDENTITY, -offset),
+        }
+    }
+
+    // A zero factor flattens the object, its inverse is then infinite like Scaled's
+    pub fn scaling(scale: Vec3) -> Self {
+        let diagonal = |s: Vec3| Mat3::new([[s.x, 0.0, 0.0], [0.0, s.y, 0.0], [0.0, 0.0, s.z]]);
+        Self {
+            m: affine(&diagonal(scale), Vec3::ZERO),
+            inv: affine(&diagonal(scale.map(|s| 1.0 / s)), Vec3::ZERO),
+        }
+    }
+
+    pub fn rotation(rotation: Quat) -> Self {
+        let matrix = rotation.normalized().to_mat3();
+        Self {
+            m: affine(&matrix, Vec3::ZERO),
+            inv: affine(&matrix.transpose(), Vec3::ZERO),
+        }
+    }
+
+    // Counterclockwise about an axis through the origin, looking against it
+    pub fn rotation_about_axis(axis: Vec3, degrees: f64) -> Self {
+        Self::rotation(Quat::from_axis_angle(axis, degrees.to_radians()))
+    }
+
+    pub fn rotation_about(pivot: Point3, axis: Vec3, degrees: f64) -> Self {
+        Self::about(pivot, Self::rotation_about_axis(axis, degrees))
+    }
+
+    pub fn scaling_about(pivot: Point3, scale: Vec3) -> Self {
+        Self::about(pivot, Self::scaling(scale))
+    }
+
+    // Translation, then rotation, then stretch, read right to left
+    // None when the stretch is singular
+    pub fn compose(translation: Vec3, rotation: Quat, stretch: Mat3) -> Option<Self> {
+        let rotation = rotation.normalized().to_mat3();
+        Self::from_parts(&rotation * &stretch, translation)
+    }
+
+    // `transform` with pivot in place of the origin
+    fn about(pivot: Point3, transform: Transform) -> Self {
+        Self::translation(-pivot)
+            .then(&transform)
+            .then(&Self::translation(pivot))
+    }
+
+    // This transform, followed by `next`
+    pub fn then(&self, next: &Transform) -> Transform {
+        Self {
+            m: mul(&next.m, &self.m),
+            inv: mul(&self.inv, &next.inv),
+        }
+    }
+
+    pub fn inverse(&self) -> Transform {
+        Self {
+            m: self.inv,
+            inv: self.m,
+        }
+    }
+
+    pub fn matrix(&self) -> &[[f64; 4]; 4] {
+        &self.m
+    }
+
+    // Exactly, steps that only cancel out up to rounding are not
+    pub fn is_identity(&self) -> bool {
+        self.m == IDENTITY4
+    }
+
+    pub fn linear(&self) -> Mat3 {
+        linear_part(&self.m)
+    }
+
+    pub fn translation_part(&self) -> Vec3 {
+        Vec3::new(self.m[0][3], self.m[1][3], self.m[2][3])
+    }
+
+    pub fn point(&self, p: Point3) -> Point3 {
+        apply(&self.m, p, 1.0)
+    }
+
+    pub fn vector(&self, v: Vec3) -> Vec3 {
+        apply(&self.m, v, 0.0)
+    }
+
+    // Normals go through the inverse transpose, the result is not unit length
+    pub fn normal(&self, n: Vec3) -> Vec3 {
+        let m = &self.inv;
+        Vec3::new(
+            m[0][0] * n.x + m[1][0] * n.y + m[2][0] * n.z,
+            m[0][1] * n.x + m[1][1] * n.y + m[2][1] * n.z,
+            m[0][2] * n.x + m[1][2] * n.y + m[2][2] * n.z,
+        )
+    }
+
+    pub fn ray(&self, ray: &Ray) -> Ray {
+        Ray::with_time(self.point(ray.origin), self.vector(ray.direction), ray.time)
+    }
+
+    // Ray into the space the transform starts from
+    pub fn inverse_ray(&self, ray: &Ray) -> Ray {
+        Ray::with_time(
+            apply(&self.inv, ray.origin, 1.0),
+            apply(&self.inv, ray.direction, 0.0),
+            ray.time,
+        )
+    }
+
+    /*
+        Translation, rotation and stretch with M = T R S
+
+        R comes from the polar decomposition of the linear part, which
+        averages it with its inverse transpose until it is orthogonal. S keeps
+        whatever scale and shear is left, so composing them gives back the
+        same transform.
+    */
+    pub fn decompose(&self) -> (Vec3, Quat, Mat3) {
+        let linear = self.linear();
+        // a reflection cannot be a rotation, it stays in the stretch
+        let sign = if linear.det() < 0.0 { -1.0 } else { 1.0 };
+        let mut rotation = linear * sign;
+        for _ in 0..100 {
+            let Some(inverse) = rotation.inverse() else {
+                break;
+            };
+            let next = (rotation + inverse.transpose()) * 0.5;
+            let change = (0..3)
+                .flat_map(|i| (0..3).map(move |j| (i, j)))
+                .map(|(i, j)| (next.m[i][j] - rotation.m[i][j]).abs())
+                .fold(0.0, f64::max);
+            rotation = next;
+            if change < 1e-12 {
+                break;
+            }
+        }
+        let stretch = &rotation.transpose() * &linear;
+        (self.translation_part(), Quat::from_mat3(&rotation), stretch)
+    }
+
+    // Moves translation and stretch in a straight line and the rotation along
+    // the shorter arc, None when the stretch passes through a singular one
+    pub fn interpolate(a: &Transform, b: &Transform, t: f64) -> Option<Transform> {
+        let (ta, ra, sa) = a.decompose();
+        let (tb, rb, sb) = b.decompose();
+        Self::compose(
+            ta * (1.0 - t) + tb * t,
+            Quat::slerp(ra, rb, t),
+            sa * (1.0 - t) + sb * t,
+        )
+    }
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+fn affine(linear: &Mat3, translation: Vec3) -> Mat4 {
+    let l = &linear.m;
+    [
+        [l[0][0], l[0][1], l[0][2], translation.x],
+        [l[1][0], l[1][1], l[1][2], translation.y],
+        [l[2][0], l[2][1], l[2][2], translation.z],
+        [0.0, 0.0, 0.0, 1.0],
+    ]
+}
+
+fn linear_part(m: &Mat4) -> Mat3 {
+    Mat3::new([
+        [m[0][0], m[0][1], m[0][2]],
+        [m[1][0], m[1][1], m[1][2]],
+        [m[2][0], m[2][1], m[2][2]],
+    ])
+}
+
+fn mul(a: &Mat4, b: &Mat4) -> Mat4 {
+    let mut result = [[0.0; 4]; 4];
+    for (i, row) in result.iter_mut().enumerate() {
+        for (j, entry) in row.iter_mut().enumerate() {
+            for k in 0..4 {
+                *entry += a[i][k] * b[k][j];
+            }
+        }
+    }
+    result
+}
+
+// w is 1 for points, 0 for vectors
+fn apply(m: &Mat4, v: Vec3, w: f64) -> Vec3 {
+    Vec3::new(
+        m[0][0] * v.x + m[0][1] * v.y + m[0][2] * v.z + m[0][3] * w,
+        m[1][0] * v.x + m[1][1] * v.y + m[1][2] * v.z + m[1][3] * w,
+        m[2][0] * v.x + m[2][1] * v.y + m[2][2] * v.z + m[2][3] * w,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: &Transform, b: &Transform) {
+        for (row_a, row_b) in a.matrix().iter().zip(b.matrix()) {
+            for (x, y) in row_a.iter().zip(row_b) {
+                assert!((x - y).abs() < 1e-9, "{a:?} != {b:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn compose_undoes_decompose() {
+        let shear = Transform::from_mat3(Mat3::new([
+            [1.0, 0.7, 0.0],
+            [0.0, 1.0, -0.4],
+            [0.2, 0.0, 1.0],
+        ]))
+        .unwrap();
+        let transforms = [
+            Transform::IDENTITY,
+            Transform::translation(Vec3::new(1.0, -2.0, 3.0)),
+            Transform::rotation_about(Point3::new(1.0, 0.0, 2.0), Vec3::new(1.0, 2.0, 3.0), 130.0),
+            Transform::scaling_about(Point3::new(0.0, 1.0, 0.0), Vec3::new(2.0, 0.5, 3.0)),
+            // a reflection stays in the stretch
+            Transform::scaling(Vec3::new(-1.0, 1.0, 1.0)),
+            Transform::rotation_about_axis(Vec3::new(0.0, 1.0, 0.0), 45.0)
+                .then(&shear)
+                .then(&Transform::translation(Vec3::new(0.5, 0.5, -4.0))),
+        ];
+        for transform in &transforms {
+            let (translation, rotation, stretch) = transform.decompose();
+            assert_close(
+                &Transform::compose(translation, rotation, stretch).unwrap(),
+                transform,
+            );
+            // polar decomposition: a proper rotation and a symmetric stretch
+            assert!((rotation.dot(rotation) - 1.0).abs() < 1e-9);
+            for i in 0..3 {
+                for j in 0..3 {
+                    assert!((stretch.m[i][j] - stretch.m[j][i]).abs() < 1e-9);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn interpolate_hits_both_ends() {
+        let a = Transform::rotation_about_axis(Vec3::new(0.0, 0.0, 1.0), 20.0);
+        let b = Transform::scaling(Vec3::new(2.0, 1.0, 1.0))
+            .then(&Transform::rotation_about_axis(
+                Vec3::new(1.0, 0.0, 0.0),
+                170.0,
+            ))
+            .then(&Transform::translation(Vec3::new(0.0, 3.0, 0.0)));
+        assert_close(&Transform::interpolate(&a, &b, 0.0).unwrap(), &a);
+        assert_close(&Transform::interpolate(&a, &b, 1.0).unwrap(), &b);
+    }
+}
@@ -1,4 +1,6 @@
-use crate::base::Axis;
+use std::ops::Range;
+
+use crate::base::{Axis, Rng, sample_uniform_sphere};
 
 #[derive(Clone, Copy, Debug)]
 pub struct Vec3 {
@@ -10,9 +12,17 @@ pub struct Vec3 {
 pub type Point3 = Vec3;
 
 impl Vec3 {
-    pub const ZERO: Self = Self { x: 0.0, y: 0.0, z: 0.0 };
-    pub const ONE: Self = Self { x: 1.0, y: 1.0, z: 1.0 };
-    
+    pub const ZERO: Self = Self {
+        x: 0.0,
+        y: 0.0,
+        z: 0.0,
+    };
+    pub const ONE: Self = Self {
+        x: 1.0,
+        y: 1.0,
+        z: 1.0,
+    };
+
     pub const fn new(x: f64, y: f64, z: f64) -> Self {
         Self { x, y, z }
     }
@@ -31,6 +41,10 @@ impl Vec3 {
         (self.x.abs() < s) && (self.y.abs() < s) && (self.z.abs() < s)
     }
 
+    pub fn max_component(self) -> f64 {
+        self.x.max(self.y).max(self.z)
+    }
+
     pub fn dot(self, other: Self) -> f64 {
         self.x * other.x + self.y * other.y + self.z * other.z
     }
@@ -47,31 +61,27 @@ impl Vec3 {
         self / self.length()
     }
 
+    // Applies f to every component
+    pub fn map(self, f: impl Fn(f64) -> f64) -> Self {
+        Self::new(f(self.x), f(self.y), f(self.z))
+    }
+
     // --------------------- random algorithm ---------------------
-    pub fn random(range: impl rand::distr::uniform::SampleRange<f64> + Clone) -> Self {
-        use rand::random_range;
+    pub fn random(rng: &mut Rng, range: Range<f64>) -> Self {
         Self::new(
-            random_range(range.clone()),
-            random_range(range.clone()),
-            random_range(range.clone()),
+            rng.random_range(range.clone()),
+            rng.random_range(range.clone()),
+            rng.random_range(range),
         )
     }
 
-    /*
-        Random x,y,z by Standard Normal Distribution
-        The result unit vector on disk will be in Standard Uniform Distribution
-    */
-    pub fn random_unit_vector() -> Self {
-        Self::new(
-            random_normal_f64(),
-            random_normal_f64(),
-            random_normal_f64(),
-        )
-        .unit_vector()
+    // Uniform on the unit sphere
+    pub fn random_unit_vector(rng: &mut Rng) -> Self {
+        sample_uniform_sphere(rng.random_2d())
     }
 
-    pub fn random_on_hemisphere(normal: Self) -> Self {
-        let on_unit_sphere = Self::random_unit_vector();
+    pub fn random_on_hemisphere(rng: &mut Rng, normal: Self) -> Self {
+        let on_unit_sphere = Self::random_unit_vector(rng);
         if on_unit_sphere.dot(normal) > 0.0 {
             on_unit_sphere
         } else {
@@ -207,9 +217,3 @@ impl std::ops::Neg for Vec3 {
         Vec3::new(-self.x, -self.y, -self.z)
     }
 }
-
-fn random_normal_f64() -> f64 {
-    use rand_distr::{Distribution, Normal};
-    let normal = Normal::new(0.0, 1.0).unwrap();
-    normal.sample(&mut rand::rng())
-}
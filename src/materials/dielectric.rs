@@ -1,4 +1,4 @@
-use crate::materials::{Material, Scatter};
+use crate::materials::{BsdfSample, Material};
 use crate::objects::Hit;
 use crate::prelude::*;
 
@@ -7,14 +7,15 @@ pub struct Dielectric {
 }
 
 impl Dielectric {
+    #[allow(clippy::new_ret_no_self)]
     pub fn new(ior_ratio: f64) -> Arc<dyn Material> {
         Arc::new(Self { ior_ratio })
     }
 }
 
 impl Material for Dielectric {
-    fn scatter(&self, ray_in: &Ray, hit: &Hit) -> Option<Scatter> {
-        let attenuation = Color::new(1.0, 1.0, 1.0);
+    // Reflects or refracts with the Fresnel odds, both are delta lobes
+    fn sample(&self, ray_in: &Ray, hit: &Hit, rng: &mut Rng) -> Option<BsdfSample> {
         let ior_ratio: f64 = if hit.front_face {
             1.0 / self.ior_ratio
         } else {
@@ -27,17 +28,33 @@ impl Material for Dielectric {
 
         let cannot_refract = ior_ratio * sin_theta > 1.0;
 
-        let direction = if cannot_refract || reflectance(cos_theta, ior_ratio) > random_unit_f64() {
-            unit_direction.reflect(hit.normal)
+        let reflect_odds = if cannot_refract {
+            1.0
         } else {
-            unit_direction.refract(hit.normal, ior_ratio)
+            reflectance(cos_theta, ior_ratio)
         };
 
-        Some(Scatter {
-            ray_out: Ray::with_time(hit.point, direction, ray_in.time),
-            attenuation,
+        let (direction, pdf) = if cannot_refract || reflect_odds > rng.random_f64() {
+            (unit_direction.reflect(hit.normal), reflect_odds)
+        } else {
+            (
+                unit_direction.refract(hit.normal, ior_ratio),
+                1.0 - reflect_odds,
+            )
+        };
+
+        Some(BsdfSample {
+            direction: direction.unit_vector(),
+            weight: Color::new(1.0, 1.0, 1.0),
+            pdf,
+            delta: true,
         })
     }
+
+    // clear glass, everything is let through
+    fn albedo(&self, _hit: &Hit) -> Color {
+        Color::new(1.0, 1.0, 1.0)
+    }
 }
 
 fn reflectance(cos: f64, ior_ratio: f64) -> f64 {
@@ -1,4 +1,4 @@
-use crate::materials::{Material, Scatter};
+use crate::materials::{BsdfSample, Material};
 use crate::objects::Hit;
 use crate::prelude::*;
 
@@ -13,17 +13,32 @@ pub struct Isotropic {
 }
 
 impl Isotropic {
+    #[allow(clippy::new_ret_no_self)]
     pub fn new(albedo: Color) -> Arc<dyn Material> {
         Arc::new(Self { albedo })
     }
 }
 
 impl Material for Isotropic {
-    fn scatter(&self, ray_in: &Ray, hit: &Hit) -> Option<Scatter> {
-        let scattered = Ray::with_time(hit.point, Vec3::random_unit_vector(), ray_in.time);
-        Some(Scatter {
-            ray_out: scattered,
-            attenuation: self.albedo,
+    // Uniform over the sphere of directions
+    fn sample(&self, _ray_in: &Ray, _hit: &Hit, rng: &mut Rng) -> Option<BsdfSample> {
+        Some(BsdfSample {
+            direction: sample_uniform_sphere(rng.random_2d()),
+            weight: self.albedo,
+            pdf: uniform_sphere_pdf(),
+            delta: false,
         })
     }
+
+    fn eval(&self, _ray_in: &Ray, _hit: &Hit, _direction: Vec3) -> Color {
+        self.albedo * uniform_sphere_pdf()
+    }
+
+    fn pdf(&self, _ray_in: &Ray, _hit: &Hit, _direction: Vec3) -> f64 {
+        uniform_sphere_pdf()
+    }
+
+    fn albedo(&self, _hit: &Hit) -> Color {
+        self.albedo
+    }
 }
@@ -1,4 +1,4 @@
-use crate::materials::{Material, Scatter};
+use crate::materials::{BsdfSample, Material};
 use crate::objects::Hit;
 use crate::prelude::*;
 
@@ -7,22 +7,35 @@ pub struct Lambertian {
 }
 
 impl Lambertian {
+    #[allow(clippy::new_ret_no_self)]
     pub fn new(albedo: Color) -> Arc<dyn Material> {
         Arc::new(Self { albedo })
     }
 }
 
 impl Material for Lambertian {
-    fn scatter(&self, ray_in: &Ray, hit: &Hit) -> Option<Scatter> {
-        let mut scatter_direction = hit.normal + Vec3::random_unit_vector();
+    // Cosine weighted, so the weight is just the albedo
+    fn sample(&self, _ray_in: &Ray, hit: &Hit, rng: &mut Rng) -> Option<BsdfSample> {
+        let local = sample_cosine_hemisphere(rng.random_2d());
+        Some(BsdfSample {
+            direction: Onb::from_w(hit.normal).to_world(local),
+            weight: self.albedo,
+            pdf: cosine_hemisphere_pdf(local.z),
+            delta: false,
+        })
+    }
+
+    // albedo / pi times the cosine
+    fn eval(&self, _ray_in: &Ray, hit: &Hit, direction: Vec3) -> Color {
+        let cosine = hit.normal.dot(direction.unit_vector());
+        self.albedo * cosine_hemisphere_pdf(cosine)
+    }
 
-        if scatter_direction.near_zero() {
-            scatter_direction = hit.normal;
-        }
+    fn pdf(&self, _ray_in: &Ray, hit: &Hit, direction: Vec3) -> f64 {
+        cosine_hemisphere_pdf(hit.normal.dot(direction.unit_vector()))
+    }
 
-        Some(Scatter {
-            ray_out: Ray::with_time(hit.point, scatter_direction, ray_in.time),
-            attenuation: self.albedo,
-        })
+    fn albedo(&self, _hit: &Hit) -> Color {
+        self.albedo
     }
 }
@@ -1,4 +1,5 @@
 use crate::materials::Material;
+use crate::objects::Hit;
 use crate::prelude::*;
 
 pub struct DiffuseLight {
@@ -6,6 +7,7 @@ pub struct DiffuseLight {
 }
 
 impl DiffuseLight {
+    #[allow(clippy::new_ret_no_self)]
     pub fn new(emit: Color) -> Arc<dyn Material> {
         Arc::new(DiffuseLight { color: emit })
     }
@@ -15,4 +17,18 @@ impl Material for DiffuseLight {
     fn emitted(&self, _: Point3) -> Color {
         self.color
     }
+
+    fn is_emissive(&self) -> bool {
+        true
+    }
+
+    // the hue of the light, scaled into [0, 1]
+    fn albedo(&self, _hit: &Hit) -> Color {
+        let max = self.color.max_component();
+        if max > 0.0 {
+            self.color / max
+        } else {
+            self.color
+        }
+    }
 }
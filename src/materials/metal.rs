@@ -1,4 +1,4 @@
-use crate::materials::{Material, Scatter};
+use crate::materials::{BsdfSample, Material};
 use crate::objects::Hit;
 use crate::prelude::*;
 
@@ -12,6 +12,7 @@ impl Metal {
     pub const GOLD_ALBEDO: Color = Color::new(255.0 / 256.0, 226.0 / 256.0, 155.0 / 256.0);
     pub const CHROME_ALBEDO: Color = Color::new(196.0 / 255.0, 197.0 / 255.0, 197.0 / 255.0);
 
+    #[allow(clippy::new_ret_no_self)]
     pub fn new(albedo: Color) -> Arc<dyn Material> {
         Arc::new(Self { albedo, fuzz: 0.0 })
     }
@@ -25,12 +26,71 @@ impl Metal {
 }
 
 impl Material for Metal {
-    fn scatter(&self, ray_in: &Ray, hit: &Hit) -> Option<Scatter> {
-        let mut reflected: Vec3 = ray_in.direction.reflect(hit.normal);
-        reflected = reflected.unit_vector() + (self.fuzz * Vec3::random_unit_vector());
-        Some(Scatter {
-            ray_out: Ray::with_time(hit.point, reflected, ray_in.time),
-            attenuation: self.albedo,
+    // Mirror direction pushed to a random point on a sphere of radius fuzz around it
+    fn sample(&self, ray_in: &Ray, hit: &Hit, rng: &mut Rng) -> Option<BsdfSample> {
+        let reflected = ray_in.direction.reflect(hit.normal).unit_vector();
+        if self.fuzz == 0.0 {
+            return Some(BsdfSample {
+                direction: reflected,
+                weight: self.albedo,
+                pdf: 1.0,
+                delta: true,
+            });
+        }
+
+        let fuzzed = reflected + (self.fuzz * sample_uniform_sphere(rng.random_2d()));
+        if fuzzed.near_zero() {
+            return None;
+        }
+        let direction = fuzzed.unit_vector();
+        // fuzzed into the surface, absorbed
+        if hit.normal.dot(direction) <= 0.0 {
+            return None;
+        }
+        Some(BsdfSample {
+            direction,
+            weight: self.albedo,
+            pdf: self.pdf(ray_in, hit, direction),
+            delta: false,
         })
     }
+
+    // The weight is always the albedo, so eval = albedo * pdf
+    fn eval(&self, ray_in: &Ray, hit: &Hit, direction: Vec3) -> Color {
+        self.albedo * self.pdf(ray_in, hit, direction)
+    }
+
+    // 0 below the surface, those directions are absorbed
+    fn pdf(&self, ray_in: &Ray, hit: &Hit, direction: Vec3) -> f64 {
+        if self.fuzz == 0.0 || hit.normal.dot(direction) <= 0.0 {
+            return 0.0;
+        }
+        let reflected = ray_in.direction.reflect(hit.normal).unit_vector();
+        fuzz_sphere_pdf(reflected, self.fuzz, direction.unit_vector())
+    }
+
+    fn albedo(&self, _hit: &Hit) -> Color {
+        self.albedo
+    }
+}
+
+/*
+    Density of the direction towards a uniform point on the sphere of radius
+    `fuzz` around the unit vector `center`. The direction crosses the sphere at
+    t^2 - 2t(d.c) + 1 - fuzz^2 = 0, and each crossing adds its area density
+    1 / (4 pi fuzz^2) times t^2 / |cos| to the solid angle density.
+*/
+fn fuzz_sphere_pdf(center: Vec3, fuzz: f64, direction: Vec3) -> f64 {
+    let b = direction.dot(center);
+    let discriminant = b * b - (1.0 - fuzz * fuzz);
+    if discriminant <= 0.0 {
+        return 0.0;
+    }
+    let root = discriminant.sqrt();
+    // |cos| at both crossings is root / fuzz
+    [b - root, b + root]
+        .iter()
+        .filter(|&&t| t > 0.0)
+        .map(|t| t * t / (4.0 * PI * fuzz * root))
+        .sum()
 }
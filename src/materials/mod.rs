@@ -13,17 +13,54 @@ pub use metal::Metal;
 use crate::objects::Hit;
 use crate::prelude::*;
 
+/*
+    A material is described by its BSDF:
+        sample  picks an outgoing direction for light leaving along -ray_in
+        eval    how much of the light arriving from a direction goes out along -ray_in
+        pdf     density of sample picking a direction
+
+    eval already includes the cosine at the surface, so for a sampled direction
+    weight = eval / pdf. Delta lobes (mirrors, glass) can only be sampled,
+    their eval and pdf are 0 for every direction.
+*/
+
 pub trait Material: Send + Sync {
-    fn scatter(&self, _ray_in: &Ray, _hit: &Hit) -> Option<Scatter> {
+    // None when the light is absorbed
+    fn sample(&self, _ray_in: &Ray, _hit: &Hit, _rng: &mut Rng) -> Option<BsdfSample> {
         None
     }
 
+    // BSDF times the cosine towards `direction`, the phase function for media
+    fn eval(&self, _ray_in: &Ray, _hit: &Hit, _direction: Vec3) -> Color {
+        Color::ZERO
+    }
+
+    // Solid angle density of sample returning `direction`
+    fn pdf(&self, _ray_in: &Ray, _hit: &Hit, _direction: Vec3) -> f64 {
+        0.0
+    }
+
     fn emitted(&self, _point: Point3) -> Color {
         Color::new(0.0, 0.0, 0.0)
     }
+
+    // Whether objects made of it are collected as lights
+    fn is_emissive(&self) -> bool {
+        false
+    }
+
+    // Reflectance at the hit, independent of the lighting (albedo AOV)
+    fn albedo(&self, _hit: &Hit) -> Color {
+        Color::new(0.0, 0.0, 0.0)
+    }
 }
 
-pub struct Scatter {
-    pub ray_out: Ray,
-    pub attenuation: Color,
+pub struct BsdfSample {
+    // unit vector
+    pub direction: Vec3,
+    // eval / pdf, what the light from `direction` is multiplied by
+    pub weight: Color,
+    // not a density for delta lobes, only the odds of picking this one
+    pub pdf: f64,
+    pub delta: bool,
 }
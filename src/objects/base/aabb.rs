@@ -1,3 +1,4 @@
+use std::cell::Cell;
 use std::ops::{Add, Mul, Sub};
 
 use crate::prelude::*;
@@ -7,6 +8,16 @@ use crate::prelude::*;
     Use for Bvh optimizing
 */
 
+thread_local! {
+    static BOX_TESTS: Cell<u64> = const { Cell::new(0) };
+}
+
+// Bounding boxes tested on the current thread so far, a measure of traversal cost
+// Always 0 without the "stats" feature
+pub fn box_tests() -> u64 {
+    BOX_TESTS.with(Cell::get)
+}
+
 #[derive(Clone, Copy)]
 pub struct Aabb {
     pub x: Interval,
@@ -38,6 +49,27 @@ impl Aabb {
         )
     }
 
+    pub fn corners(&self) -> [Point3; 8] {
+        let (x, y, z) = (self.x, self.y, self.z);
+        [
+            Point3::new(x.min, y.min, z.min),
+            Point3::new(x.max, y.min, z.min),
+            Point3::new(x.min, y.max, z.min),
+            Point3::new(x.max, y.max, z.min),
+            Point3::new(x.min, y.min, z.max),
+            Point3::new(x.max, y.min, z.max),
+            Point3::new(x.min, y.max, z.max),
+            Point3::new(x.max, y.max, z.max),
+        ]
+    }
+
+    // Smallest box holding all the points
+    pub fn around(points: impl IntoIterator<Item = Point3>) -> Self {
+        points.into_iter().fold(Self::EMPTY, |aabb, p| {
+            Self::enclosing(aabb, Self::from_corners(p, p))
+        })
+    }
+
     pub const fn axis(&self, axis: Axis) -> Interval {
         match axis {
             Axis::X => self.x,
@@ -47,6 +79,9 @@ impl Aabb {
     }
 
     pub fn hit(&self, ray: &Ray, t_range: Interval) -> bool {
+        if cfg!(feature = "stats") {
+            BOX_TESTS.with(|tests| tests.set(tests.get() + 1));
+        }
         let mut lower_bound = t_range.min;
         let mut upper_bound = t_range.max;
 
@@ -144,16 +179,17 @@ impl Mul<Aabb> for Vec3 {
     }
 }
 
+// Every corner is transformed, a rotated box is not spanned by two of them
 impl Mul<Aabb> for &Mat3 {
     type Output = Aabb;
     fn mul(self, rhs: Aabb) -> Self::Output {
-        let min_pos = self * Vec3::new(rhs.x.min, rhs.y.min, rhs.z.min);
-        let max_pos = self * Vec3::new(rhs.x.max, rhs.y.max, rhs.z.max);
+        Aabb::around(rhs.corners().map(|corner| self * corner))
+    }
+}
 
-        Aabb::new(
-            Interval::new(min_pos.x, max_pos.x),
-            Interval::new(min_pos.y, max_pos.y),
-            Interval::new(min_pos.z, max_pos.z),
-        )
+impl Mul<Aabb> for &Transform {
+    type Output = Aabb;
+    fn mul(self, rhs: Aabb) -> Self::Output {
+        Aabb::around(rhs.corners().map(|corner| self.point(corner)))
     }
 }
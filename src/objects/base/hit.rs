@@ -10,6 +10,12 @@ pub struct Hit {
     pub mat: Arc<dyn Material>,
     pub t: f64,
     pub front_face: bool,
+    // ids of the object that was hit and of its material, see SceneIds
+    pub object_id: u32,
+    pub material_id: u32,
+    // surface coordinates in [0, 1], 0 for objects without any
+    pub u: f64,
+    pub v: f64,
 }
 
 impl Hit {
@@ -19,6 +25,8 @@ impl Hit {
         outward_normal: Vec3,
         mat: Arc<dyn Material>,
         t: f64,
+        object_id: u32,
+        material_id: u32,
     ) -> Self {
         let front_face = ray.direction.dot(outward_normal) < 0.0;
         let normal = if front_face {
@@ -35,6 +43,14 @@ impl Hit {
             mat,
             t,
             front_face,
+            object_id,
+            material_id,
+            u: 0.0,
+            v: 0.0,
         }
     }
+
+    pub fn with_uv(self, u: f64, v: f64) -> Self {
+        Self { u, v, ..self }
+    }
 }
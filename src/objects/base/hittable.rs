@@ -1,9 +1,60 @@
-use crate::objects::base::{Aabb, Hit};
+use std::cell::Cell;
+use std::collections::HashMap;
+
+use crate::materials::Material;
+use crate::objects::base::{Aabb, Hit, Light};
 use crate::prelude::*;
 
+/*
+    Object and material ids of one scene, handed out when the World is built
+    so they only depend on the scene and not on what else the process made.
+    0 is left for "nothing was hit" and "no material".
+*/
+#[derive(Default)]
+pub struct SceneIds {
+    objects: u32,
+    // materials seen so far, a shared material keeps one id
+    materials: HashMap<*const (), u32>,
+}
+
+impl SceneIds {
+    pub fn object(&mut self) -> u32 {
+        self.objects += 1;
+        self.objects
+    }
+
+    pub fn material(&mut self, mat: &Arc<dyn Material>) -> u32 {
+        let key = Arc::as_ptr(mat) as *const ();
+        let next = self.materials.len() as u32 + 1;
+        *self.materials.entry(key).or_insert(next)
+    }
+}
+
+thread_local! {
+    static INTERSECTION_TESTS: Cell<u64> = const { Cell::new(0) };
+}
+
+// Rays tested against primitives (spheres, quads) on the current thread so far
+pub fn intersection_tests() -> u64 {
+    INTERSECTION_TESTS.with(Cell::get)
+}
+
+// Compiled out without the "stats" feature
+pub(crate) fn count_intersection_test() {
+    if cfg!(feature = "stats") {
+        INTERSECTION_TESTS.with(|tests| tests.set(tests.get() + 1));
+    }
+}
+
 pub trait Hittable: Sync {
-    fn hit(&self, ray: &Ray, t_range: Interval) -> Option<Hit>;
+    fn hit(&self, ray: &Ray, t_range: Interval, rng: &mut Rng) -> Option<Hit>;
     fn bounding_box(&self) -> Aabb;
+
+    // Adds the emitters of this object that can be sampled directly
+    fn collect_lights(&self, _lights: &mut Vec<Arc<dyn Light>>) {}
+
+    // Gives this object and the objects inside it their ids, see SceneIds
+    fn assign_ids(&mut self, _ids: &mut SceneIds) {}
 }
 
 pub fn to_hittable(object: impl Hittable + 'static) -> Box<dyn Hittable> {
@@ -11,11 +62,19 @@ pub fn to_hittable(object: impl Hittable + 'static) -> Box<dyn Hittable> {
 }
 
 impl<T: Hittable + ?Sized> Hittable for Box<T> {
-    fn hit(&self, ray: &Ray, t_range: Interval) -> Option<Hit> {
-        (**self).hit(&ray, t_range)
+    fn hit(&self, ray: &Ray, t_range: Interval, rng: &mut Rng) -> Option<Hit> {
+        (**self).hit(ray, t_range, rng)
     }
 
     fn bounding_box(&self) -> Aabb {
         (**self).bounding_box()
     }
+
+    fn collect_lights(&self, lights: &mut Vec<Arc<dyn Light>>) {
+        (**self).collect_lights(lights)
+    }
+
+    fn assign_ids(&mut self, ids: &mut SceneIds) {
+        (**self).assign_ids(ids)
+    }
 }
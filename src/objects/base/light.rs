@@ -0,0 +1,32 @@
+use crate::prelude::*;
+
+/*
+    Emitters the renderer can aim shadow rays at (next-event estimation)
+
+    Objects with an emissive material hand themselves out through
+    Hittable::collect_lights. Fixed instances hand out their object's lights
+    sampled through the transform. Lights inside blocks or moving instances
+    are not collected, since a light has no time to be sampled at; they are
+    still found by bouncing into them.
+*/
+
+pub trait Light: Send + Sync {
+    // Random point on the light as seen from `origin`, None if nothing can be seen
+    fn sample(&self, origin: Point3, rng: &mut Rng) -> Option<LightSample>;
+
+    // Solid angle density of `sample` picking `direction` from `origin`, 0 if it misses the light
+    fn pdf(&self, origin: Point3, direction: Vec3) -> f64;
+
+    // Same as the object_id of hits on the light
+    fn object_id(&self) -> u32;
+}
+
+pub struct LightSample {
+    pub point: Point3,
+    // unit vector from the origin towards the point
+    pub direction: Vec3,
+    pub distance: f64,
+    // solid angle density
+    pub pdf: f64,
+    pub emission: Color,
+}
@@ -1,7 +1,10 @@
 mod aabb;
 mod hit;
 mod hittable;
+mod light;
 
-pub use aabb::Aabb;
+pub use aabb::{Aabb, box_tests};
 pub use hit::Hit;
-pub use hittable::{Hittable, to_hittable};
+pub(crate) use hittable::count_intersection_test;
+pub use hittable::{Hittable, SceneIds, intersection_tests, to_hittable};
+pub use light::{Light, LightSample};
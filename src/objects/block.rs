@@ -5,6 +5,8 @@ use crate::prelude::*;
 
 pub struct Block {
     faces: HittableList,
+    // the faces are one object
+    id: u32,
 }
 
 impl Block {
@@ -67,16 +69,24 @@ impl Block {
             mat.clone(),
         ));
 
-        Self { faces }
+        Self { faces, id: 0 }
     }
 }
 
 impl Hittable for Block {
-    fn hit(&self, ray: &Ray, t_range: Interval) -> Option<Hit> {
-        self.faces.hit(ray, t_range)
+    fn hit(&self, ray: &Ray, t_range: Interval, rng: &mut Rng) -> Option<Hit> {
+        let mut hit = self.faces.hit(ray, t_range, rng)?;
+        hit.object_id = self.id;
+        Some(hit)
     }
 
     fn bounding_box(&self) -> Aabb {
         self.faces.bounding_box()
     }
+
+    fn assign_ids(&mut self, ids: &mut SceneIds) {
+        // the faces keep their material ids, the object id is the block's
+        self.faces.assign_ids(ids);
+        self.id = ids.object();
+    }
 }
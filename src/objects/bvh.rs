@@ -27,7 +27,7 @@ impl Bvh {
 
     pub fn new(mut objects: Vec<Box<dyn Hittable>>) -> Self {
         let mut bbox = Aabb::EMPTY;
-        
+
         for obj in objects.iter() {
             bbox = Aabb::enclosing(bbox, obj.bounding_box());
         }
@@ -45,7 +45,7 @@ impl Bvh {
                 }
             }
             _ => {
-                objects.sort_by(|a, b| box_compare(a, b, axis));
+                objects.sort_by(|a, b| box_compare(a.as_ref(), b.as_ref(), axis));
                 let mid = size / 2;
                 let right_vec = objects.split_off(mid);
                 let left = Box::new(Self::new(objects));
@@ -58,15 +58,15 @@ impl Bvh {
 }
 
 impl Hittable for Bvh {
-    fn hit(&self, ray: &Ray, t_range: Interval) -> Option<Hit> {
+    fn hit(&self, ray: &Ray, t_range: Interval, rng: &mut Rng) -> Option<Hit> {
         if !self.bounding_box().hit(ray, t_range) {
             return None;
         }
 
         match self {
             Self::Node { left, right, .. } => {
-                let hit_left = left.hit(&ray, t_range);
-                let hit_right = right.hit(&ray, t_range);
+                let hit_left = left.hit(ray, t_range, rng);
+                let hit_right = right.hit(ray, t_range, rng);
                 match (hit_left, hit_right) {
                     (Some(l), Some(r)) => {
                         if l.t < r.t {
@@ -80,7 +80,7 @@ impl Hittable for Bvh {
                     (None, None) => None,
                 }
             }
-            Self::Leaf(leaf) => leaf.hit(&ray, t_range),
+            Self::Leaf(leaf) => leaf.hit(ray, t_range, rng),
         }
     }
 
@@ -90,9 +90,29 @@ impl Hittable for Bvh {
             Self::Leaf(leaf) => leaf.bounding_box(),
         }
     }
+
+    fn collect_lights(&self, lights: &mut Vec<Arc<dyn Light>>) {
+        match self {
+            Self::Node { left, right, .. } => {
+                left.collect_lights(lights);
+                right.collect_lights(lights);
+            }
+            Self::Leaf(leaf) => leaf.collect_lights(lights),
+        }
+    }
+
+    fn assign_ids(&mut self, ids: &mut SceneIds) {
+        match self {
+            Self::Node { left, right, .. } => {
+                left.assign_ids(ids);
+                right.assign_ids(ids);
+            }
+            Self::Leaf(leaf) => leaf.assign_ids(ids),
+        }
+    }
 }
 
-fn box_compare(a: &Box<dyn Hittable>, b: &Box<dyn Hittable>, axis: Axis) -> Ordering {
+fn box_compare(a: &dyn Hittable, b: &dyn Hittable, axis: Axis) -> Ordering {
     let a_axis_interval = a.bounding_box().axis(axis);
     let b_axis_interval = b.bounding_box().axis(axis);
     a_axis_interval
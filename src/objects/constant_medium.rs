@@ -9,6 +9,8 @@ pub struct ConstantMedium<T> {
     neg_inv_density: f64,
     phase_function: Arc<dyn Material>,
     bbox: Aabb,
+    id: u32,
+    material_id: u32,
 }
 
 impl<T: Hittable> ConstantMedium<T> {
@@ -18,21 +20,18 @@ impl<T: Hittable> ConstantMedium<T> {
             phase_function: Isotropic::new(albedo),
             bbox: boundary.bounding_box(),
             boundary,
+            id: 0,
+            material_id: 0,
         }
     }
 }
 
 impl<T: Hittable> Hittable for ConstantMedium<T> {
-    fn hit(&self, ray: &Ray, t_range: Interval) -> Option<Hit> {
-        let Some(mut hit1) = self.boundary.hit(ray, Interval::UNIVERSE) else {
-            return None;
-        };
-        let Some(mut hit2) = self
+    fn hit(&self, ray: &Ray, t_range: Interval, rng: &mut Rng) -> Option<Hit> {
+        let mut hit1 = self.boundary.hit(ray, Interval::UNIVERSE, rng)?;
+        let mut hit2 = self
             .boundary
-            .hit(ray, Interval::new(hit1.t + EPSILON, INFINITY))
-        else {
-            return None;
-        };
+            .hit(ray, Interval::new(hit1.t + EPSILON, INFINITY), rng)?;
 
         if hit1.t < t_range.min {
             hit1.t = t_range.min;
@@ -51,7 +50,7 @@ impl<T: Hittable> Hittable for ConstantMedium<T> {
         let ray_lenght: f64 = ray.direction.length();
         // I found it here typo -> total reflection -> kinetice theory of gas is crying
         let distance_inside_boundary: f64 = (hit2.t - hit1.t) * ray_lenght;
-        let hit_distance: f64 = self.neg_inv_density * random_unit_f64().ln();
+        let hit_distance: f64 = self.neg_inv_density * rng.random_f64().ln();
 
         // forgot to return HERE, GENPHYS is crying now
         if hit_distance > distance_inside_boundary {
@@ -67,9 +66,17 @@ impl<T: Hittable> Hittable for ConstantMedium<T> {
             Vec3::new(1.0, 0.0, 0.0),
             self.phase_function.clone(),
             t,
+            self.id,
+            self.material_id,
         ))
     }
     fn bounding_box(&self) -> Aabb {
         self.bbox
     }
+
+    fn assign_ids(&mut self, ids: &mut SceneIds) {
+        self.boundary.assign_ids(ids);
+        self.id = ids.object();
+        self.material_id = ids.material(&self.phase_function);
+    }
 }
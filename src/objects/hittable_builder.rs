@@ -8,141 +8,246 @@ use crate::prelude::*;
 //     .translate(Vec3::ZERO)
 //     .rotate_x(23.0)
 //     .build();
+// Fixed steps are composed into one Transform, so the object ends up in a
+// single Transformed however long the chain is, or in none when the steps
+// leave it where it was. Moving steps wrap what was built so far and start a
+// new transform after it.
 pub struct HittableBuilder<T> {
     object: T,
+    transform: Transform,
 }
 
 impl<T: Hittable> HittableBuilder<T> {
     pub fn new(object: T) -> Self {
-        Self { object }
+        Self {
+            object,
+            transform: Transform::IDENTITY,
+        }
     }
 
-    pub fn build(self) -> T {
-        self.object
+    pub fn build(self) -> Placed<T> {
+        if self.transform.is_identity() {
+            Placed::AsIs(self.object)
+        } else {
+            Placed::Transformed(Transformed::new(self.object, self.transform))
+        }
     }
 
-    pub fn translate(self, offset: Vec3) -> HittableBuilder<Translated<T>> {
-        HittableBuilder::new(Translated::new(self.object, offset))
+    // Applies `transform` after the steps so far
+    pub fn transform(self, transform: Transform) -> Self {
+        Self {
+            transform: self.transform.then(&transform),
+            ..self
+        }
     }
 
-    pub fn translating(self, offset: Vec3) -> HittableBuilder<Translating<T>> {
-        HittableBuilder::new(Translating::new(
-            self.object,
-            Vec3::ZERO,
-            offset,
-        ))
+    pub fn translate(self, offset: Vec3) -> Self {
+        self.transform(Transform::translation(offset))
     }
 
-    pub fn rotate_x_about(self, pivot: Point3, degrees: f64) -> HittableBuilder<Rotated<T>> {
-        HittableBuilder::new(Rotated::new(self.object, pivot, Axis::X, degrees))
+    pub fn translating(self, offset: Vec3) -> HittableBuilder<Translating<Placed<T>>> {
+        HittableBuilder::new(Translating::new(self.build(), Vec3::ZERO, offset))
     }
 
-    pub fn rotate_y_about(self, pivot: Point3, degrees: f64) -> HittableBuilder<Rotated<T>> {
-        HittableBuilder::new(Rotated::new(self.object, pivot, Axis::Y, degrees))
+    pub fn translating_along(self, offset: Track<Vec3>) -> HittableBuilder<Translating<Placed<T>>> {
+        HittableBuilder::new(Translating::along(self.build(), offset))
     }
 
-    pub fn rotate_z_about(self, pivot: Point3, degrees: f64) -> HittableBuilder<Rotated<T>> {
-        HittableBuilder::new(Rotated::new(self.object, pivot, Axis::Z, degrees))
+    // About any axis through pivot
+    pub fn rotate_about(self, pivot: Point3, axis: Vec3, degrees: f64) -> Self {
+        self.transform(Transform::rotation_about(pivot, axis, degrees))
     }
 
-    pub fn rotate_x(self, degrees: f64) -> HittableBuilder<Rotated<T>> {
+    pub fn rotate_x_about(self, pivot: Point3, degrees: f64) -> Self {
+        self.rotate_about(pivot, Axis::X.direction(), degrees)
+    }
+
+    pub fn rotate_y_about(self, pivot: Point3, degrees: f64) -> Self {
+        self.rotate_about(pivot, Axis::Y.direction(), degrees)
+    }
+
+    pub fn rotate_z_about(self, pivot: Point3, degrees: f64) -> Self {
+        self.rotate_about(pivot, Axis::Z.direction(), degrees)
+    }
+
+    pub fn rotate_x(self, degrees: f64) -> Self {
         self.rotate_x_about(Point3::ZERO, degrees)
     }
 
-    pub fn rotate_y(self, degrees: f64) -> HittableBuilder<Rotated<T>> {
+    pub fn rotate_y(self, degrees: f64) -> Self {
         self.rotate_y_about(Point3::ZERO, degrees)
     }
 
-    pub fn rotate_z(self, degrees: f64) -> HittableBuilder<Rotated<T>> {
+    pub fn rotate_z(self, degrees: f64) -> Self {
         self.rotate_z_about(Point3::ZERO, degrees)
     }
 
-    pub fn rotating_x_about(self, pivot: Point3, degrees: f64) -> HittableBuilder<Rotating<T>> {
-        HittableBuilder::new(Rotating::new(self.object, pivot, Axis::X, 0.0, degrees))
+    pub fn rotating_x_about(
+        self,
+        pivot: Point3,
+        degrees: f64,
+    ) -> HittableBuilder<Rotating<Placed<T>>> {
+        HittableBuilder::new(Rotating::new(self.build(), pivot, Axis::X, 0.0, degrees))
     }
 
-    pub fn rotating_y_about(self, pivot: Point3, degrees: f64) -> HittableBuilder<Rotating<T>> {
-        HittableBuilder::new(Rotating::new(self.object, pivot, Axis::Y, 0.0, degrees))
+    pub fn rotating_y_about(
+        self,
+        pivot: Point3,
+        degrees: f64,
+    ) -> HittableBuilder<Rotating<Placed<T>>> {
+        HittableBuilder::new(Rotating::new(self.build(), pivot, Axis::Y, 0.0, degrees))
     }
 
-    pub fn rotating_z_about(self, pivot: Point3, degrees: f64) -> HittableBuilder<Rotating<T>> {
-        HittableBuilder::new(Rotating::new(self.object, pivot, Axis::Z, 0.0, degrees))
+    pub fn rotating_z_about(
+        self,
+        pivot: Point3,
+        degrees: f64,
+    ) -> HittableBuilder<Rotating<Placed<T>>> {
+        HittableBuilder::new(Rotating::new(self.build(), pivot, Axis::Z, 0.0, degrees))
     }
 
-    pub fn rotating_x(self, degrees: f64) -> HittableBuilder<Rotating<T>> {
+    pub fn rotating_x(self, degrees: f64) -> HittableBuilder<Rotating<Placed<T>>> {
         self.rotating_x_about(Point3::ZERO, degrees)
     }
 
-    pub fn rotating_y(self, degrees: f64) -> HittableBuilder<Rotating<T>> {
+    pub fn rotating_y(self, degrees: f64) -> HittableBuilder<Rotating<Placed<T>>> {
         self.rotating_y_about(Point3::ZERO, degrees)
     }
 
-    pub fn rotating_z(self, degrees: f64) -> HittableBuilder<Rotating<T>> {
+    pub fn rotating_z(self, degrees: f64) -> HittableBuilder<Rotating<Placed<T>>> {
         self.rotating_z_about(Point3::ZERO, degrees)
     }
 
-    pub fn scale_at(self, pivot: Point3, scale: Vec3) -> HittableBuilder<Scaled<T>> {
-        HittableBuilder::new(Scaled::new(self.object, scale, pivot))
+    pub fn rotating_along(
+        self,
+        pivot: Point3,
+        axis: Axis,
+        degrees: Track<f64>,
+    ) -> HittableBuilder<Rotating<Placed<T>>> {
+        HittableBuilder::new(Rotating::along(self.build(), pivot, axis, degrees))
     }
 
-    pub fn scale(self, ratio: f64) -> HittableBuilder<Scaled<T>> {
+    pub fn scale_at(self, pivot: Point3, scale: Vec3) -> Self {
+        self.transform(Transform::scaling_about(pivot, scale))
+    }
+
+    pub fn scale(self, ratio: f64) -> Self {
         self.scale_at(Point3::ZERO, Vec3::new(ratio, ratio, ratio))
     }
 
-    pub fn scale_x_at(self, pivot: Point3, s: f64) -> HittableBuilder<Scaled<T>> {
+    pub fn scale_x_at(self, pivot: Point3, s: f64) -> Self {
         self.scale_at(pivot, Vec3::new(s, 1.0, 1.0))
     }
 
-    pub fn scale_y_at(self, pivot: Point3, s: f64) -> HittableBuilder<Scaled<T>> {
+    pub fn scale_y_at(self, pivot: Point3, s: f64) -> Self {
         self.scale_at(pivot, Vec3::new(1.0, s, 1.0))
     }
 
-    pub fn scale_z_at(self, pivot: Point3, s: f64) -> HittableBuilder<Scaled<T>> {
+    pub fn scale_z_at(self, pivot: Point3, s: f64) -> Self {
         self.scale_at(pivot, Vec3::new(1.0, 1.0, s))
     }
 
-    pub fn scale_x(self, s: f64) -> HittableBuilder<Scaled<T>> {
+    pub fn scale_x(self, s: f64) -> Self {
         self.scale_at(Point3::ZERO, Vec3::new(s, 1.0, 1.0))
     }
 
-    pub fn scale_y(self, s: f64) -> HittableBuilder<Scaled<T>> {
+    pub fn scale_y(self, s: f64) -> Self {
         self.scale_at(Point3::ZERO, Vec3::new(1.0, s, 1.0))
     }
 
-    pub fn scale_z(self, s: f64) -> HittableBuilder<Scaled<T>> {
+    pub fn scale_z(self, s: f64) -> Self {
         self.scale_at(Point3::ZERO, Vec3::new(1.0, 1.0, s))
     }
 
-    pub fn scaling_at(self, start: Vec3, end: Vec3, pivot: Point3) -> HittableBuilder<Scaling<T>> {
-        HittableBuilder::new(Scaling::new(self.object, start, end, pivot))
+    pub fn scaling_at(
+        self,
+        start: Vec3,
+        end: Vec3,
+        pivot: Point3,
+    ) -> HittableBuilder<Scaling<Placed<T>>> {
+        HittableBuilder::new(Scaling::new(self.build(), pivot, start, end))
     }
 
-    pub fn scaling(self, ratio: f64) -> HittableBuilder<Scaling<T>> {
+    pub fn scaling(self, ratio: f64) -> HittableBuilder<Scaling<Placed<T>>> {
         self.scaling_at(Vec3::ONE, Vec3::new(ratio, ratio, ratio), Point3::ZERO)
     }
 
-    pub fn scaling_x(self, s: f64) -> HittableBuilder<Scaling<T>> {
+    pub fn scaling_x(self, s: f64) -> HittableBuilder<Scaling<Placed<T>>> {
         self.scaling_at(Vec3::ONE, Vec3::new(s, 1.0, 1.0), Point3::ZERO)
     }
 
-    pub fn scaling_y(self, s: f64) -> HittableBuilder<Scaling<T>> {
+    pub fn scaling_y(self, s: f64) -> HittableBuilder<Scaling<Placed<T>>> {
         self.scaling_at(Vec3::ONE, Vec3::new(1.0, s, 1.0), Point3::ZERO)
     }
 
-    pub fn scaling_z(self, s: f64) -> HittableBuilder<Scaling<T>> {
+    pub fn scaling_z(self, s: f64) -> HittableBuilder<Scaling<Placed<T>>> {
         self.scaling_at(Vec3::ONE, Vec3::new(1.0, 1.0, s), Point3::ZERO)
     }
 
-    pub fn scaling_x_at(self, pivot: Point3, end_s: f64) -> HittableBuilder<Scaling<T>> {
+    pub fn scaling_x_at(self, pivot: Point3, end_s: f64) -> HittableBuilder<Scaling<Placed<T>>> {
         self.scaling_at(Vec3::ONE, Vec3::new(end_s, 1.0, 1.0), pivot)
     }
 
-    pub fn scaling_y_at(self, pivot: Point3, end_s: f64) -> HittableBuilder<Scaling<T>> {
+    pub fn scaling_y_at(self, pivot: Point3, end_s: f64) -> HittableBuilder<Scaling<Placed<T>>> {
         self.scaling_at(Vec3::ONE, Vec3::new(1.0, end_s, 1.0), pivot)
     }
 
-    pub fn scaling_z_at(self, pivot: Point3, end_s: f64) -> HittableBuilder<Scaling<T>> {
+    pub fn scaling_z_at(self, pivot: Point3, end_s: f64) -> HittableBuilder<Scaling<Placed<T>>> {
         self.scaling_at(Vec3::ONE, Vec3::new(1.0, 1.0, end_s), pivot)
     }
-    
+
+    pub fn scaling_along(
+        self,
+        pivot: Point3,
+        scale: Track<Vec3>,
+    ) -> HittableBuilder<Scaling<Placed<T>>> {
+        HittableBuilder::new(Scaling::along(self.build(), pivot, scale))
+    }
+
+    // From where the steps so far left the object through the transforms of the keys
+    pub fn transforming_along(
+        self,
+        keys: Track<Transform>,
+    ) -> HittableBuilder<Transforming<Placed<T>>> {
+        HittableBuilder::new(Transforming::along(self.build(), keys))
+    }
+}
+
+// Object where the fixed steps of a HittableBuilder put it
+// Built once per object, the size of the identity case does not matter
+#[allow(clippy::large_enum_variant)]
+pub enum Placed<T> {
+    // the steps did not move it
+    AsIs(T),
+    Transformed(Transformed<T>),
+}
+
+impl<T: Hittable> Hittable for Placed<T> {
+    fn hit(&self, ray: &Ray, t_range: Interval, rng: &mut Rng) -> Option<Hit> {
+        match self {
+            Self::AsIs(object) => object.hit(ray, t_range, rng),
+            Self::Transformed(object) => object.hit(ray, t_range, rng),
+        }
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        match self {
+            Self::AsIs(object) => object.bounding_box(),
+            Self::Transformed(object) => object.bounding_box(),
+        }
+    }
+
+    fn collect_lights(&self, lights: &mut Vec<Arc<dyn Light>>) {
+        match self {
+            Self::AsIs(object) => object.collect_lights(lights),
+            Self::Transformed(object) => object.collect_lights(lights),
+        }
+    }
+
+    fn assign_ids(&mut self, ids: &mut SceneIds) {
+        match self {
+            Self::AsIs(object) => object.assign_ids(ids),
+            Self::Transformed(object) => object.assign_ids(ids),
+        }
+    }
 }
@@ -36,13 +36,19 @@ impl HittableList {
     }
 }
 
+impl Default for HittableList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Hittable for HittableList {
-    fn hit(&self, ray: &Ray, t_range: Interval) -> Option<Hit> {
+    fn hit(&self, ray: &Ray, t_range: Interval, rng: &mut Rng) -> Option<Hit> {
         let mut closest_hit = None;
         let mut closest_so_far = t_range.max;
 
         for object in &self.objects {
-            if let Some(hit) = object.hit(ray, Interval::new(t_range.min, closest_so_far)) {
+            if let Some(hit) = object.hit(ray, Interval::new(t_range.min, closest_so_far), rng) {
                 closest_so_far = hit.t;
                 closest_hit = Some(hit);
             }
@@ -54,4 +60,16 @@ impl Hittable for HittableList {
     fn bounding_box(&self) -> Aabb {
         self.bbox
     }
+
+    fn collect_lights(&self, lights: &mut Vec<Arc<dyn Light>>) {
+        for object in &self.objects {
+            object.collect_lights(lights);
+        }
+    }
+
+    fn assign_ids(&mut self, ids: &mut SceneIds) {
+        for object in &mut self.objects {
+            object.assign_ids(ids);
+        }
+    }
 }
@@ -7,3 +7,5 @@ pub use rotate::{Rotated, Rotating};
 pub use scale::{Scaled, Scaling};
 pub use transform::{Transformed, Transforming};
 pub use translate::{Translated, Translating};
+
+use transform::collect_transformed_lights;
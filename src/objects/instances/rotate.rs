@@ -1,4 +1,5 @@
 use crate::objects::base::*;
+use crate::objects::instances::collect_transformed_lights;
 use crate::prelude::*;
 
 pub struct Rotated<T> {
@@ -46,7 +47,7 @@ impl<T: Hittable> Rotated<T> {
 }
 
 impl<T: Hittable> Hittable for Rotated<T> {
-    fn hit(&self, ray: &Ray, t_range: Interval) -> Option<Hit> {
+    fn hit(&self, ray: &Ray, t_range: Interval, rng: &mut Rng) -> Option<Hit> {
         let reference_point = ray.at(1.0);
         let rotated_origin = get_rotated(
             ray.origin,
@@ -65,7 +66,7 @@ impl<T: Hittable> Hittable for Rotated<T> {
         let rotated_ray =
             Ray::with_time(rotated_origin, rotated_reference - rotated_origin, ray.time);
 
-        self.object.hit(&rotated_ray, t_range).map(|mut hit| {
+        self.object.hit(&rotated_ray, t_range, rng).map(|mut hit| {
             let new_point = get_rotated(
                 hit.point,
                 self.pivot,
@@ -91,20 +92,42 @@ impl<T: Hittable> Hittable for Rotated<T> {
     fn bounding_box(&self) -> Aabb {
         self.bbox
     }
+
+    fn collect_lights(&self, lights: &mut Vec<Arc<dyn Light>>) {
+        let degrees = self.sin_theta.atan2(self.cos_theta).to_degrees();
+        let transform = Transform::rotation_about(self.pivot, self.axis.direction(), degrees);
+        collect_transformed_lights(&self.object, &transform, lights);
+    }
+
+    fn assign_ids(&mut self, ids: &mut SceneIds) {
+        self.object.assign_ids(ids);
+    }
 }
 
 pub struct Rotating<T> {
     object: T,
     axis: Axis,
     pivot: Point3,
-    angle: Lerp<f64>,
+    // radians
+    angle: Track<f64>,
     bbox: Aabb,
 }
 
 impl<T: Hittable> Rotating<T> {
     pub fn new(object: T, pivot: Point3, axis: Axis, degrees1: f64, degrees2: f64) -> Self {
-        let theta1 = degrees1.to_radians();
-        let theta2 = degrees2.to_radians();
+        Self::along(object, pivot, axis, Track::linear(degrees1, degrees2))
+    }
+
+    // Turns object by the angles of the track, in degrees
+    pub fn along(object: T, pivot: Point3, axis: Axis, degrees: Track<f64>) -> Self {
+        let angle = degrees.map(f64::to_radians);
+        // every angle the track reaches lies between these
+        let (theta1, theta2) = angle
+            .hull()
+            .into_iter()
+            .fold((INFINITY, -INFINITY), |(low, high), theta| {
+                (low.min(theta), high.max(theta))
+            });
         let bbox = object.bounding_box();
 
         let mut global_min = Point3::new(INFINITY, INFINITY, INFINITY);
@@ -129,14 +152,20 @@ impl<T: Hittable> Rotating<T> {
             object,
             pivot,
             axis,
-            angle: Lerp::new(theta1, theta2),
+            angle,
             bbox: Aabb::from_corners(global_min, global_max),
         }
     }
+
+    // Turns during `times` of the timeline instead of [0, 1]
+    pub fn during(mut self, times: Interval) -> Self {
+        self.angle = self.angle.during(times);
+        self
+    }
 }
 
 impl<T: Hittable> Hittable for Rotating<T> {
-    fn hit(&self, ray: &Ray, t_range: Interval) -> Option<Hit> {
+    fn hit(&self, ray: &Ray, t_range: Interval, rng: &mut Rng) -> Option<Hit> {
         let theta = self.angle.at(ray.time);
         let sin_theta = theta.sin();
         let cos_theta = theta.cos();
@@ -155,7 +184,7 @@ impl<T: Hittable> Hittable for Rotating<T> {
         let rotated_ray =
             Ray::with_time(rotated_origin, rotated_reference - rotated_origin, ray.time);
 
-        self.object.hit(&rotated_ray, t_range).map(|mut hit| {
+        self.object.hit(&rotated_ray, t_range, rng).map(|mut hit| {
             let new_point = get_rotated(hit.point, self.pivot, self.axis, sin_theta, cos_theta);
             let reference_point = hit.point + hit.normal;
             let new_reference_point =
@@ -170,6 +199,10 @@ impl<T: Hittable> Hittable for Rotating<T> {
     fn bounding_box(&self) -> Aabb {
         self.bbox
     }
+
+    fn assign_ids(&mut self, ids: &mut SceneIds) {
+        self.object.assign_ids(ids);
+    }
 }
 
 // rotated along axis counterclockwise
@@ -191,7 +224,7 @@ fn get_rotated(point: Point3, pivot: Point3, axis: Axis, sin_theta: f64, cos_the
     rotated
 }
 
-// by ChatGPT
+// Box of the corner turned by every angle in [theta1, theta2], theta1 <= theta2
 fn swept_aabb_3d(
     corner: Point3,
     pivot: Point3,
@@ -201,36 +234,20 @@ fn swept_aabb_3d(
 ) -> (Point3, Point3) {
     let prev_axis = axis.prev();
     let next_axis = axis.next();
-
-    // Candidate angles where x (prev_axis) or y (next_axis) extrema occur
-    let theta_prev = (-(corner.axis(next_axis) - pivot.axis(next_axis))
-        / (corner.axis(prev_axis) - pivot.axis(prev_axis)))
-    .atan(); // simplified, might use atan2
-    let theta_next = ((corner.axis(prev_axis) - pivot.axis(prev_axis))
-        / (corner.axis(next_axis) - pivot.axis(next_axis)))
-    .atan();
+    let dp = corner.axis(prev_axis) - pivot.axis(prev_axis);
+    let dn = corner.axis(next_axis) - pivot.axis(next_axis);
+    // past a full turn the box stays the same
+    let theta2 = theta2.min(theta1 + 2.0 * PI);
 
     // Include endpoints
     let mut angles = vec![theta1, theta2];
 
-    // Add extrema angles if they are inside [theta1, theta2]
-    for theta in [theta_prev, theta_next] {
-        let t = theta % (2.0 * std::f64::consts::PI);
-        let t1 = theta1 % (2.0 * std::f64::consts::PI);
-        let t2 = theta2 % (2.0 * std::f64::consts::PI);
-        let t2 = if t2 < t1 {
-            t2 + 2.0 * std::f64::consts::PI
-        } else {
-            t2
-        };
-        let t = if t < t1 {
-            t + 2.0 * std::f64::consts::PI
-        } else {
-            t
-        };
-        if t1 <= t && t <= t2 {
-            angles.push(theta);
-        }
+    // The prev_axis coordinate peaks where tan(theta) = dn / dp, the next_axis
+    // one where tan(theta) = -dp / dn, every half turn from there
+    for base in [dn.atan2(dp), (-dp).atan2(dn)] {
+        let first = ((theta1 - base) / PI).ceil() as i64;
+        let last = ((theta2 - base) / PI).floor() as i64;
+        angles.extend((first..=last).map(|k| base + k as f64 * PI));
     }
 
     let mut min = Point3::new(INFINITY, INFINITY, INFINITY);
@@ -1,4 +1,5 @@
 use crate::objects::base::*;
+use crate::objects::instances::collect_transformed_lights;
 use crate::prelude::*;
 
 pub struct Scaled<T> {
@@ -21,13 +22,13 @@ impl<T: Hittable> Scaled<T> {
 }
 
 impl<T: Hittable> Hittable for Scaled<T> {
-    fn hit(&self, ray: &Ray, t_range: Interval) -> Option<Hit> {
+    fn hit(&self, ray: &Ray, t_range: Interval, rng: &mut Rng) -> Option<Hit> {
         let scaled_ray = Ray::with_time(
             (ray.origin - self.center) / self.scale + self.center,
             ray.direction / self.scale,
             ray.time,
         );
-        self.object.hit(&scaled_ray, t_range).map(|mut hit| {
+        self.object.hit(&scaled_ray, t_range, rng).map(|mut hit| {
             hit.point = self.scale * (hit.point - self.center) + self.center;
             hit.normal = (hit.normal / self.scale).unit_vector();
             hit
@@ -37,37 +38,58 @@ impl<T: Hittable> Hittable for Scaled<T> {
     fn bounding_box(&self) -> Aabb {
         self.bbox
     }
+
+    fn collect_lights(&self, lights: &mut Vec<Arc<dyn Light>>) {
+        let transform = Transform::scaling_about(self.center, self.scale);
+        collect_transformed_lights(&self.object, &transform, lights);
+    }
+
+    fn assign_ids(&mut self, ids: &mut SceneIds) {
+        self.object.assign_ids(ids);
+    }
 }
 
 pub struct Scaling<T> {
     object: T,
-    scale: Lerp<Vec3>,
+    scale: Track<Vec3>,
     center: Vec3,
     bbox: Aabb,
 }
 
 impl<T: Hittable> Scaling<T> {
     pub fn new(object: T, center: Vec3, scale1: Vec3, scale2: Vec3) -> Self {
-        let bbox1 = scale1 * (object.bounding_box() - center) + center;
-        let bbox2 = scale2 * (object.bounding_box() - center) + center;
+        Self::along(object, center, Track::linear(scale1, scale2))
+    }
+
+    // Scales about center by the factors of the track
+    pub fn along(object: T, center: Vec3, scale: Track<Vec3>) -> Self {
+        let bbox = scale.hull().into_iter().fold(Aabb::EMPTY, |bbox, at| {
+            Aabb::enclosing(bbox, at * (object.bounding_box() - center) + center)
+        });
         Self {
             object,
-            scale: Lerp::new(scale1, scale2),
+            scale,
             center,
-            bbox: Aabb::enclosing(bbox1, bbox2),
+            bbox,
         }
     }
+
+    // Grows during `times` instead of [0, 1]
+    pub fn during(mut self, times: Interval) -> Self {
+        self.scale = self.scale.during(times);
+        self
+    }
 }
 
 impl<T: Hittable> Hittable for Scaling<T> {
-    fn hit(&self, ray: &Ray, t_range: Interval) -> Option<Hit> {
+    fn hit(&self, ray: &Ray, t_range: Interval, rng: &mut Rng) -> Option<Hit> {
         let scale = self.scale.at(ray.time);
         let scaled_ray = Ray::with_time(
             (ray.origin - self.center) / scale + self.center,
             ray.direction / scale,
             ray.time,
         );
-        self.object.hit(&scaled_ray, t_range).map(|mut hit| {
+        self.object.hit(&scaled_ray, t_range, rng).map(|mut hit| {
             hit.point = scale * (hit.point - self.center) + self.center;
             hit.normal = (hit.normal / scale).unit_vector();
             hit
@@ -77,4 +99,8 @@ impl<T: Hittable> Hittable for Scaling<T> {
     fn bounding_box(&self) -> Aabb {
         self.bbox
     }
+
+    fn assign_ids(&mut self, ids: &mut SceneIds) {
+        self.object.assign_ids(ids);
+    }
 }
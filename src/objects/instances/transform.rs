@@ -3,81 +3,290 @@ use crate::prelude::*;
 
 pub struct Transformed<T> {
     object: T,
-    transform: Mat3,
-    inv: Mat3,
+    transform: Transform,
     bbox: Aabb,
 }
 
 impl<T: Hittable> Transformed<T> {
-    pub fn new(object: T, transform: Mat3) -> Option<Self> {
+    pub fn new(object: T, transform: Transform) -> Self {
         let bbox = &transform * object.bounding_box();
-        let inv = transform.inverse();
-
-        inv.map(|inv| Self {
+        Self {
             object,
             transform,
-            inv,
             bbox,
-        })
+        }
     }
 }
 
 impl<T: Hittable> Hittable for Transformed<T> {
-    fn hit(&self, ray: &crate::prelude::Ray, t_range: crate::prelude::Interval) -> Option<Hit> {
-        let transformed_ray =
-            Ray::with_time(&self.inv * ray.origin, &self.inv * ray.direction, ray.time);
-
-        self.object.hit(&transformed_ray, t_range).map(|mut hit| {
-            hit.point = &self.transform * hit.point;
-            hit.normal = (&self.inv.transpose() * hit.normal).unit_vector();
-            hit
-        })
+    fn hit(&self, ray: &Ray, t_range: Interval, rng: &mut Rng) -> Option<Hit> {
+        let transformed_ray = self.transform.inverse_ray(ray);
+        self.object
+            .hit(&transformed_ray, t_range, rng)
+            .map(|mut hit| {
+                hit.point = self.transform.point(hit.point);
+                hit.normal = self.transform.normal(hit.normal).unit_vector();
+                hit
+            })
     }
 
     fn bounding_box(&self) -> Aabb {
         self.bbox
     }
+
+    fn collect_lights(&self, lights: &mut Vec<Arc<dyn Light>>) {
+        collect_transformed_lights(&self.object, &self.transform, lights);
+    }
+
+    fn assign_ids(&mut self, ids: &mut SceneIds) {
+        self.object.assign_ids(ids);
+    }
+}
+
+/*
+    Light of an object inside a fixed transform
+
+    Sampling happens in the object's space. A linear map M stretches the
+    solid angle around a unit direction w by |det M| / |M w|^3, so the
+    densities are divided by that on the way out.
+*/
+struct TransformedLight {
+    light: Arc<dyn Light>,
+    transform: Transform,
+}
+
+impl TransformedLight {
+    fn world_pdf(&self, pdf: f64, local_direction: Vec3) -> f64 {
+        let stretch = self.transform.vector(local_direction).length();
+        pdf * stretch.powi(3) / self.transform.linear().det().abs()
+    }
 }
 
+impl Light for TransformedLight {
+    fn sample(&self, origin: Point3, rng: &mut Rng) -> Option<LightSample> {
+        let local = self
+            .light
+            .sample(self.transform.inverse().point(origin), rng)?;
+        let point = self.transform.point(local.point);
+        let distance = (point - origin).length();
+        Some(LightSample {
+            point,
+            direction: (point - origin) / distance,
+            distance,
+            pdf: self.world_pdf(local.pdf, local.direction),
+            emission: local.emission,
+        })
+    }
+
+    fn pdf(&self, origin: Point3, direction: Vec3) -> f64 {
+        let inverse = self.transform.inverse();
+        let local_direction = inverse.vector(direction).unit_vector();
+        let pdf = self.light.pdf(inverse.point(origin), local_direction);
+        self.world_pdf(pdf, local_direction)
+    }
+
+    fn object_id(&self) -> u32 {
+        self.light.object_id()
+    }
+}
+
+// Lights of `object` as seen through `transform`, none when it flattens the object
+pub(super) fn collect_transformed_lights(
+    object: &dyn Hittable,
+    transform: &Transform,
+    lights: &mut Vec<Arc<dyn Light>>,
+) {
+    if transform.linear().det() == 0.0 {
+        return;
+    }
+    let mut inner = Vec::new();
+    object.collect_lights(&mut inner);
+    for light in inner {
+        lights.push(Arc::new(TransformedLight {
+            light,
+            transform: *transform,
+        }));
+    }
+}
+
+/*
+    Transform moving over time, kept as translation, rotation and stretch
+    tracks (see Transform::decompose) so a turn does not shear halfway
+*/
 pub struct Transforming<T> {
     object: T,
-    transform: Lerp<Mat3>,
+    translation: Track<Vec3>,
+    rotation: Track<Quat>,
+    stretch: Track<Mat3>,
     bbox: Aabb,
 }
 
 impl<T: Hittable> Transforming<T> {
-    pub fn new(object: T, transform1: Mat3, transform2: Mat3) -> Option<Self> {
-        let bbox1 = &transform1 * object.bounding_box();
-        let bbox2 = &transform2 * object.bounding_box();
-
-        let inv1 = transform1.inverse();
-        let inv2 = transform2.inverse();
-
-        if !(inv1.is_none() || inv2.is_none()) {
-            Some(Self {
-                object,
-                transform: Lerp::new(transform1, transform2),
-                bbox: Aabb::enclosing(bbox1, bbox2),
-            })
-        } else {
-            None
+    pub fn new(object: T, transform1: Transform, transform2: Transform) -> Self {
+        Self::along(object, Track::linear(transform1, transform2))
+    }
+
+    // Key transforms are decomposed, Linear keys turn by slerp
+    // A tangent of a whole transform has no parts to go with, so keys with one
+    // panic; Hermite keys without ease in and out, give tangents per part
+    // through from_tracks instead
+    pub fn along(object: T, keys: Track<Transform>) -> Self {
+        let keys = keys.keys();
+        assert!(
+            keys.iter().all(|key| key.tangent.is_none()),
+            "Transforming::along cannot split key tangents, use from_tracks with a tangent per part"
+        );
+        let parts: Vec<_> = keys.iter().map(|key| key.value.decompose()).collect();
+        // each rotation on the side of the one before, so blends take the short way
+        let mut rotations: Vec<Quat> = Vec::with_capacity(parts.len());
+        for &(_, rotation, _) in &parts {
+            let rotation = match rotations.last() {
+                Some(previous) if previous.dot(rotation) < 0.0 => rotation.negated(),
+                _ => rotation,
+            };
+            rotations.push(rotation);
+        }
+        let translations: Vec<Vec3> = parts.iter().map(|part| part.0).collect();
+        let stretches: Vec<Mat3> = parts.iter().map(|part| part.2).collect();
+
+        Self::from_tracks(
+            object,
+            track_of(keys, &translations, false),
+            track_of(keys, &rotations, true),
+            track_of(keys, &stretches, false),
+        )
+    }
+
+    // The object is stretched, then turned, then moved
+    pub fn from_tracks(
+        object: T,
+        translation: Track<Vec3>,
+        rotation: Track<Quat>,
+        stretch: Track<Mat3>,
+    ) -> Self {
+        let bbox = swept_bounds(object.bounding_box(), &translation, &rotation, &stretch);
+        Self {
+            object,
+            translation,
+            rotation,
+            stretch,
+            bbox,
         }
     }
+
+    // Blends the transforms during `times` instead of [0, 1]
+    pub fn during(mut self, times: Interval) -> Self {
+        self.translation = self.translation.during(times);
+        self.rotation = self.rotation.during(times);
+        self.stretch = self.stretch.during(times);
+        self
+    }
+
+    pub fn at(&self, time: f64) -> Option<Transform> {
+        Transform::compose(
+            self.translation.at(time),
+            self.rotation.at(time),
+            self.stretch.at(time),
+        )
+    }
 }
 
 impl<T: Hittable> Hittable for Transforming<T> {
-    fn hit(&self, ray: &crate::prelude::Ray, t_range: crate::prelude::Interval) -> Option<Hit> {
-        let transform = self.transform.at(ray.time);
-        let inv = transform.inverse().unwrap();
-        let transformed_ray = Ray::with_time(&inv * ray.origin, &inv * ray.direction, ray.time);
-        self.object.hit(&transformed_ray, t_range).map(|mut hit| {
-            hit.point = &transform * hit.point;
-            hit.normal = (&inv.transpose() * hit.normal).unit_vector();
-            hit
-        })
+    fn hit(&self, ray: &Ray, t_range: Interval, rng: &mut Rng) -> Option<Hit> {
+        // a stretch passing through zero flattens the object away
+        let transform = self.at(ray.time)?;
+        let transformed_ray = transform.inverse_ray(ray);
+        self.object
+            .hit(&transformed_ray, t_range, rng)
+            .map(|mut hit| {
+                hit.point = transform.point(hit.point);
+                hit.normal = transform.normal(hit.normal).unit_vector();
+                hit
+            })
     }
 
     fn bounding_box(&self) -> Aabb {
         self.bbox
     }
+
+    fn assign_ids(&mut self, ids: &mut SceneIds) {
+        self.object.assign_ids(ids);
+    }
+}
+
+// Track through `values` at the times of `keys`
+fn track_of<T: Copy, U: Copy>(keys: &[Key<T>], values: &[U], rotation: bool) -> Track<U> {
+    let mut track = Track::new(keys[0].time, values[0]);
+    for (i, key) in keys.iter().enumerate().skip(1) {
+        let interpolation = match keys[i - 1].interpolation {
+            Interpolation::Linear if rotation => Interpolation::Slerp,
+            Interpolation::Slerp if !rotation => Interpolation::Linear,
+            interpolation => interpolation,
+        };
+        track = track.then(interpolation, key.time, values[i]);
+    }
+    track
+}
+
+/*
+    Box of every corner through the whole motion
+
+    A corner c goes to T + R S c. The stretched corners lie in the hull of
+    the stretch track's hull applied to c, turning them by every matrix of
+    the rotation hull covers R, and the translation hull adds on top.
+*/
+fn swept_bounds(
+    bbox: Aabb,
+    translation: &Track<Vec3>,
+    rotation: &Track<Quat>,
+    stretch: &Track<Mat3>,
+) -> Aabb {
+    let stretched: Vec<Vec3> = stretch
+        .hull()
+        .iter()
+        .flat_map(|s| bbox.corners().map(|corner| s * corner))
+        .collect();
+    let turned = match rotation_hull(rotation) {
+        Some(rotations) => Aabb::around(
+            rotations
+                .iter()
+                .flat_map(|r| stretched.iter().map(move |&v| r * v)),
+        ),
+        // any way around, at the farthest distance
+        None => {
+            let reach = stretched.iter().map(|v| v.length()).fold(0.0, f64::max);
+            Aabb::from_corners(
+                Vec3::new(-reach, -reach, -reach),
+                Vec3::new(reach, reach, reach),
+            )
+        }
+    };
+    let moved = Aabb::around(translation.hull());
+    let sum = |a: Interval, b: Interval| Interval::new(a.min + b.min, a.max + b.max);
+    Aabb::new(
+        sum(turned.x, moved.x),
+        sum(turned.y, moved.y),
+        sum(turned.z, moved.z),
+    )
+}
+
+// Matrices whose hull holds every rotation of the track, None when a cubic
+// blend of the quaternions leaves the arc between two keys
+fn rotation_hull(rotation: &Track<Quat>) -> Option<Vec<Mat3>> {
+    let keys = rotation.keys();
+    let mut matrices = vec![keys[0].value.normalized().to_mat3()];
+    for pair in keys.windows(2) {
+        let a = pair[0].value.normalized().to_mat3();
+        let b = pair[1].value.normalized().to_mat3();
+        let on_arc = |y: f64| (0.0..=1.0).contains(&y);
+        match pair[0].interpolation {
+            Interpolation::Step => matrices.push(b),
+            Interpolation::Linear | Interpolation::Slerp => matrices.extend(Mat3::slerp_hull(a, b)),
+            Interpolation::Bezier(_, y1, _, y2) if on_arc(y1) && on_arc(y2) => {
+                matrices.extend(Mat3::slerp_hull(a, b))
+            }
+            _ => return None,
+        }
+    }
+    Some(matrices)
 }
@@ -1,4 +1,5 @@
 use crate::objects::base::*;
+use crate::objects::instances::collect_transformed_lights;
 use crate::prelude::*;
 
 pub struct Translated<T> {
@@ -19,49 +20,78 @@ impl<T: Hittable> Translated<T> {
 }
 
 impl<T: Hittable> Hittable for Translated<T> {
-    fn hit(&self, ray: &Ray, t_range: Interval) -> Option<Hit> {
+    fn hit(&self, ray: &Ray, t_range: Interval, rng: &mut Rng) -> Option<Hit> {
         let translated_ray = Ray::with_time(ray.origin - self.offset, ray.direction, ray.time);
-        self.object.hit(&translated_ray, t_range).map(|mut hit| {
-            hit.point += self.offset;
-            hit
-        })
+        self.object
+            .hit(&translated_ray, t_range, rng)
+            .map(|mut hit| {
+                hit.point += self.offset;
+                hit
+            })
     }
 
     fn bounding_box(&self) -> Aabb {
         self.bbox
     }
+
+    fn collect_lights(&self, lights: &mut Vec<Arc<dyn Light>>) {
+        collect_transformed_lights(&self.object, &Transform::translation(self.offset), lights);
+    }
+
+    fn assign_ids(&mut self, ids: &mut SceneIds) {
+        self.object.assign_ids(ids);
+    }
 }
 
 pub struct Translating<T> {
     object: T,
-    offset: Lerp<Vec3>,
+    offset: Track<Vec3>,
     bbox: Aabb,
 }
 
 impl<T: Hittable> Translating<T> {
-    // Moves object from from offset1 to offset2 within shutter time
+    // Moves object from offset1 at time 0 to offset2 at time 1
     pub fn new(object: T, offset1: Vec3, offset2: Vec3) -> Self {
-        let bbox1 = object.bounding_box() + offset1;
-        let bbox2 = object.bounding_box() + offset2;
+        Self::along(object, Track::linear(offset1, offset2))
+    }
+
+    // Moves object by the offsets of the track
+    pub fn along(object: T, offset: Track<Vec3>) -> Self {
+        let bbox = offset.hull().into_iter().fold(Aabb::EMPTY, |bbox, at| {
+            Aabb::enclosing(bbox, object.bounding_box() + at)
+        });
         Self {
             object,
-            offset: Lerp::new(offset1, offset2),
-            bbox: Aabb::enclosing(bbox1, bbox2),
+            offset,
+            bbox,
         }
     }
+
+    // Moves between the two absolute times instead of over [0, 1], holding
+    // still before and after them
+    pub fn during(mut self, times: Interval) -> Self {
+        self.offset = self.offset.during(times);
+        self
+    }
 }
 
 impl<T: Hittable> Hittable for Translating<T> {
-    fn hit(&self, ray: &Ray, t_range: Interval) -> Option<Hit> {
+    fn hit(&self, ray: &Ray, t_range: Interval, rng: &mut Rng) -> Option<Hit> {
         let offset = self.offset.at(ray.time);
         let translated_ray = Ray::with_time(ray.origin - offset, ray.direction, ray.time);
-        self.object.hit(&translated_ray, t_range).map(|mut hit| {
-            hit.point += offset;
-            hit
-        })
+        self.object
+            .hit(&translated_ray, t_range, rng)
+            .map(|mut hit| {
+                hit.point += offset;
+                hit
+            })
     }
 
     fn bounding_box(&self) -> Aabb {
         self.bbox
     }
+
+    fn assign_ids(&mut self, ids: &mut SceneIds) {
+        self.object.assign_ids(ids);
+    }
 }
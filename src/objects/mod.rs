@@ -12,7 +12,7 @@ pub use base::*;
 pub use block::Block;
 pub use bvh::Bvh;
 pub use constant_medium::ConstantMedium;
-pub use hittable_builder::HittableBuilder;
+pub use hittable_builder::{HittableBuilder, Placed};
 pub use hittable_list::HittableList;
 pub use instances::*;
 pub use quad::Quad;
@@ -2,6 +2,7 @@ use crate::materials::Material;
 use crate::objects::base::*;
 use crate::prelude::*;
 
+#[derive(Clone)]
 pub struct Quad {
     q: Point3,
     u: Vec3,
@@ -10,6 +11,8 @@ pub struct Quad {
     d: f64,
     mat: Arc<dyn Material>,
     bbox: Aabb,
+    id: u32,
+    material_id: u32,
 }
 
 impl Quad {
@@ -26,12 +29,15 @@ impl Quad {
             d,
             mat,
             bbox: Aabb::enclosing(bbox_diagonal1, bbox_diagonal2),
+            id: 0,
+            material_id: 0,
         }
     }
 }
 
-impl Hittable for Quad {
-    fn hit(&self, ray: &Ray, t_range: Interval) -> Option<Hit> {
+impl Quad {
+    // Distance, point and (u, v) where the ray crosses the quad
+    fn intersect(&self, ray: &Ray, t_range: Interval) -> Option<(f64, Point3, (f64, f64))> {
         let denom = self.normal.dot(ray.direction);
 
         if denom.abs() < 1e-8_f64 {
@@ -48,22 +54,92 @@ impl Hittable for Quad {
         let u_length = vec.dot(self.u);
         let v_length = vec.dot(self.v);
 
-        if Interval::new(0.0, self.u.length_squared()).contains(u_length)
-            && Interval::new(0.0, self.v.length_squared()).contains(v_length)
+        let u_squared = self.u.length_squared();
+        let v_squared = self.v.length_squared();
+        if Interval::new(0.0, u_squared).contains(u_length)
+            && Interval::new(0.0, v_squared).contains(v_length)
         {
-            Some(Hit::new(
-                ray,
-                intersection,
-                self.normal,
-                self.mat.clone(),
+            Some((
                 t,
+                intersection,
+                (u_length / u_squared, v_length / v_squared),
             ))
         } else {
             None
         }
     }
 
+    fn area(&self) -> f64 {
+        self.u.cross(self.v).length()
+    }
+}
+
+impl Hittable for Quad {
+    fn hit(&self, ray: &Ray, t_range: Interval, _rng: &mut Rng) -> Option<Hit> {
+        count_intersection_test();
+        let (t, intersection, (u, v)) = self.intersect(ray, t_range)?;
+        let hit = Hit::new(
+            ray,
+            intersection,
+            self.normal,
+            self.mat.clone(),
+            t,
+            self.id,
+            self.material_id,
+        );
+        Some(hit.with_uv(u, v))
+    }
+
     fn bounding_box(&self) -> Aabb {
         self.bbox
     }
+
+    fn collect_lights(&self, lights: &mut Vec<Arc<dyn Light>>) {
+        if self.mat.is_emissive() {
+            lights.push(Arc::new(self.clone()));
+        }
+    }
+
+    fn assign_ids(&mut self, ids: &mut SceneIds) {
+        self.id = ids.object();
+        self.material_id = ids.material(&self.mat);
+    }
+}
+
+impl Light for Quad {
+    // Uniform over the area
+    fn sample(&self, origin: Point3, rng: &mut Rng) -> Option<LightSample> {
+        let [a, b] = rng.random_2d();
+        let point = self.q + a * self.u + b * self.v;
+        let to_light = point - origin;
+        let distance = to_light.length();
+        let direction = to_light / distance;
+        let cosine = self.normal.dot(direction).abs();
+        if cosine < 1e-8 {
+            return None;
+        }
+
+        Some(LightSample {
+            point,
+            direction,
+            distance,
+            // area density turned into solid angle density
+            pdf: distance * distance / (cosine * self.area()),
+            emission: self.mat.emitted(point),
+        })
+    }
+
+    fn pdf(&self, origin: Point3, direction: Vec3) -> f64 {
+        let ray = Ray::new(origin, direction);
+        let Some((t, _, _)) = self.intersect(&ray, Interval::new(0.001, INFINITY)) else {
+            return 0.0;
+        };
+        let distance_squared = t * t * direction.length_squared();
+        let cosine = self.normal.dot(direction).abs() / direction.length();
+        distance_squared / (cosine * self.area())
+    }
+
+    fn object_id(&self) -> u32 {
+        self.id
+    }
 }
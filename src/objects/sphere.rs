@@ -8,6 +8,8 @@ pub struct Sphere {
     radius: f64,
     mat: Arc<dyn Material>,
     bbox: Aabb,
+    id: u32,
+    material_id: u32,
 }
 
 impl Sphere {
@@ -21,22 +23,22 @@ impl Sphere {
                 Interval::centered_at(center.y, radius),
                 Interval::centered_at(center.z, radius),
             ),
+            id: 0,
+            material_id: 0,
         }
     }
 }
 
-impl Hittable for Sphere {
-    fn hit(&self, ray: &Ray, t_range: Interval) -> Option<Hit> {
+impl Sphere {
+    // Nearest root of the ray with the sphere within t_range
+    fn intersect(&self, ray: &Ray, t_range: Interval) -> Option<f64> {
         /*
             ray(t) = Q+t*d ; d = direction of r
             (C-(Q+t*d))(C-(Q+t*d)) = r^2 -> find solution t
             [d*d] t^2 - [2d.dot(C-Q)] * t + [(C-Q)(C-Q) - r^2] = 0
-
             use h = -b/2 = d.dot(C-Q)
             solution root = ( h +- sqrt( h*h - a*c ) ) / a
-
         */
-
         let oc = self.center - ray.origin;
         let a = ray.direction.length_squared();
         let h = ray.direction.dot(oc);
@@ -48,7 +50,6 @@ impl Hittable for Sphere {
         }
 
         let sqrtd = discriminant.sqrt();
-
         let mut root = (h - sqrtd) / a;
         if !t_range.surrounds(root) {
             root = (h + sqrtd) / a;
@@ -56,14 +57,115 @@ impl Hittable for Sphere {
                 return None;
             }
         }
+        Some(root)
+    }
+
+    // Cosine of the half angle of the cone the sphere fills as seen from outside
+    fn cone_cosine(&self, origin: Point3) -> Option<f64> {
+        let distance_squared = (self.center - origin).length_squared();
+        let radius_squared = self.radius * self.radius;
+        if distance_squared <= radius_squared {
+            return None;
+        }
+        Some((1.0 - radius_squared / distance_squared).sqrt())
+    }
+}
 
+impl Hittable for Sphere {
+    fn hit(&self, ray: &Ray, t_range: Interval, _rng: &mut Rng) -> Option<Hit> {
+        count_intersection_test();
+        let root = self.intersect(ray, t_range)?;
         let point = ray.at(root);
         let outward_normal = (point - self.center) / self.radius;
-        let hit = Hit::new(&ray, point, outward_normal, self.mat.clone(), root);
-        Some(hit)
+        let (u, v) = sphere_uv(outward_normal);
+        let hit = Hit::new(
+            ray,
+            point,
+            outward_normal,
+            self.mat.clone(),
+            root,
+            self.id,
+            self.material_id,
+        );
+        Some(hit.with_uv(u, v))
     }
 
     fn bounding_box(&self) -> Aabb {
         self.bbox
     }
+
+    fn collect_lights(&self, lights: &mut Vec<Arc<dyn Light>>) {
+        if self.mat.is_emissive() && self.radius > 0.0 {
+            lights.push(Arc::new(self.clone()));
+        }
+    }
+
+    fn assign_ids(&mut self, ids: &mut SceneIds) {
+        self.id = ids.object();
+        self.material_id = ids.material(&self.mat);
+    }
+}
+
+impl Light for Sphere {
+    // Uniform over the cone of directions the sphere covers, or over its area from inside
+    fn sample(&self, origin: Point3, rng: &mut Rng) -> Option<LightSample> {
+        let (direction, pdf) = match self.cone_cosine(origin) {
+            Some(cos_max) => {
+                let local = sample_uniform_cone(rng.random_2d(), cos_max);
+                // cone around the direction to the center
+                let onb = Onb::from_w((self.center - origin).unit_vector());
+                (onb.to_world(local), uniform_cone_pdf(cos_max))
+            }
+            None => {
+                let point = self.center + self.radius * sample_uniform_sphere(rng.random_2d());
+                let direction = (point - origin).unit_vector();
+                let pdf = self.pdf(origin, direction);
+                if pdf == 0.0 {
+                    return None;
+                }
+                (direction, pdf)
+            }
+        };
+
+        // the sampled direction may graze past the sphere by rounding
+        let root = self.intersect(&Ray::new(origin, direction), Interval::new(0.0, INFINITY))?;
+        let point = origin + root * direction;
+        Some(LightSample {
+            point,
+            direction,
+            distance: root,
+            pdf,
+            emission: self.mat.emitted(point),
+        })
+    }
+
+    fn pdf(&self, origin: Point3, direction: Vec3) -> f64 {
+        let ray = Ray::new(origin, direction);
+        let Some(root) = self.intersect(&ray, Interval::new(0.001, INFINITY)) else {
+            return 0.0;
+        };
+        match self.cone_cosine(origin) {
+            Some(cos_max) => uniform_cone_pdf(cos_max),
+            None => {
+                // uniform area density turned into solid angle density
+                let point = ray.at(root);
+                let normal = (point - self.center) / self.radius;
+                let unit = direction.unit_vector();
+                let distance_squared = (point - origin).length_squared();
+                let area = 4.0 * PI * self.radius * self.radius;
+                distance_squared / (normal.dot(unit).abs() * area)
+            }
+        }
+    }
+
+    fn object_id(&self) -> u32 {
+        self.id
+    }
+}
+
+// u goes around the y axis starting at -x, v from the bottom pole to the top
+fn sphere_uv(p: Point3) -> (f64, f64) {
+    let theta = (-p.y).clamp(-1.0, 1.0).acos();
+    let phi = (-p.z).atan2(p.x) + PI;
+    (phi / (2.0 * PI), theta / PI)
 }
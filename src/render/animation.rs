@@ -0,0 +1,56 @@
+use std::ops::Range;
+
+use crate::render::Shutter;
+
+// Frames sampled from the scene's timeline, which is in seconds
+// Frame f starts at start_time + f / fps and sees the motion of its shutter only
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Animation {
+    pub fps: f64,
+    pub first_frame: u32,
+    pub frame_count: u32,
+    // How much of a frame the shutter stays open, 360 blurs into the next
+    // frame, 180 is the usual film look and 0 freezes the motion
+    pub shutter_angle: f64,
+    // Timeline time of frame 0
+    pub start_time: f64,
+}
+
+impl Animation {
+    pub fn new(fps: f64, frame_count: u32) -> Self {
+        Self {
+            fps,
+            frame_count,
+            ..Default::default()
+        }
+    }
+
+    // Frames at fps covering `seconds` of the timeline from start_time
+    pub fn lasting(fps: f64, seconds: f64) -> Self {
+        Self::new(fps, (seconds * fps).round() as u32)
+    }
+
+    pub fn frames(&self) -> Range<u32> {
+        self.first_frame..self.first_frame + self.frame_count
+    }
+
+    pub fn frame_time(&self, frame: u32) -> f64 {
+        self.start_time + frame as f64 / self.fps
+    }
+
+    pub fn shutter(&self, frame: u32) -> Shutter {
+        Shutter::from_angle(self.frame_time(frame), self.fps, self.shutter_angle)
+    }
+}
+
+impl Default for Animation {
+    fn default() -> Self {
+        Self {
+            fps: 24.0,
+            first_frame: 0,
+            frame_count: 1,
+            shutter_angle: 180.0,
+            start_time: 0.0,
+        }
+    }
+}
@@ -0,0 +1,256 @@
+use crate::prelude::*;
+use crate::render::{Film, Image, Resolution};
+
+/*
+    This file contains
+        1. Aov (auxiliary per-pixel buffers kept next to the color)
+        2. Surface / SurfaceStats (what the camera ray hit first)
+        3. AovView (raw values of one buffer)
+        4. AovPreview (one buffer mapped into [0, 1] for viewing)
+*/
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Aov {
+    Albedo,
+    // Shading normal, facing the camera, in [-1, 1]
+    Normal,
+    // Distance along the view direction
+    Depth,
+    // World position
+    Position,
+    ObjectId,
+    MaterialId,
+    SampleCount,
+    // Error estimate adaptive sampling works with, see Film::error
+    Error,
+}
+
+impl Aov {
+    pub const ALL: [Aov; 8] = [
+        Aov::Albedo,
+        Aov::Normal,
+        Aov::Depth,
+        Aov::Position,
+        Aov::ObjectId,
+        Aov::MaterialId,
+        Aov::SampleCount,
+        Aov::Error,
+    ];
+
+    // Used as a file name suffix
+    pub fn name(&self) -> &'static str {
+        match self {
+            Aov::Albedo => "albedo",
+            Aov::Normal => "normal",
+            Aov::Depth => "depth",
+            Aov::Position => "position",
+            Aov::ObjectId => "object_id",
+            Aov::MaterialId => "material_id",
+            Aov::SampleCount => "samples",
+            Aov::Error => "error",
+        }
+    }
+}
+
+// First hit of one camera sample
+pub struct Surface {
+    pub albedo: Color,
+    pub normal: Vec3,
+    pub position: Point3,
+    pub depth: f64,
+    pub object_id: u32,
+    pub material_id: u32,
+}
+
+// First hits of all samples of a pixel
+// Colors and vectors are averaged over the samples that hit something,
+// ids come from the first sample that did
+#[derive(Clone, Copy)]
+pub struct SurfaceStats {
+    pub hits: u32,
+    pub albedo_sum: Color,
+    pub normal_sum: Vec3,
+    pub position_sum: Point3,
+    pub depth_sum: f64,
+    pub object_id: u32,
+    pub material_id: u32,
+}
+
+impl SurfaceStats {
+    pub const fn new() -> Self {
+        Self {
+            hits: 0,
+            albedo_sum: Color::ZERO,
+            normal_sum: Vec3::ZERO,
+            position_sum: Point3::ZERO,
+            depth_sum: 0.0,
+            object_id: 0,
+            material_id: 0,
+        }
+    }
+
+    pub fn add(&mut self, surface: &Surface) {
+        if self.hits == 0 {
+            self.object_id = surface.object_id;
+            self.material_id = surface.material_id;
+        }
+        self.hits += 1;
+        self.albedo_sum += surface.albedo;
+        self.normal_sum += surface.normal;
+        self.position_sum += surface.position;
+        self.depth_sum += surface.depth;
+    }
+
+    // Zero where nothing was hit
+    fn mean(&self, sum: Vec3) -> Vec3 {
+        if self.hits == 0 {
+            Vec3::ZERO
+        } else {
+            sum / self.hits as f64
+        }
+    }
+
+    pub fn albedo(&self) -> Color {
+        self.mean(self.albedo_sum)
+    }
+
+    pub fn normal(&self) -> Vec3 {
+        let normal = self.mean(self.normal_sum);
+        if normal.near_zero() {
+            normal
+        } else {
+            normal.unit_vector()
+        }
+    }
+
+    pub fn position(&self) -> Point3 {
+        self.mean(self.position_sum)
+    }
+
+    pub fn depth(&self) -> f64 {
+        if self.hits == 0 {
+            0.0
+        } else {
+            self.depth_sum / self.hits as f64
+        }
+    }
+}
+
+impl Default for SurfaceStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Raw values, meant for HDR formats and further processing
+pub struct AovView<'a> {
+    film: &'a Film,
+    aov: Aov,
+}
+
+impl<'a> AovView<'a> {
+    pub fn new(film: &'a Film, aov: Aov) -> Self {
+        Self { film, aov }
+    }
+}
+
+impl Image for AovView<'_> {
+    fn resolution(&self) -> Resolution {
+        self.film.resolution
+    }
+
+    fn pixel(&self, i: u32, j: u32) -> Color {
+        let pixel = self.film.get(i, j);
+        let surface = &pixel.surface;
+        match self.aov {
+            Aov::Albedo => surface.albedo(),
+            Aov::Normal => surface.normal(),
+            Aov::Depth => Vec3::ONE * surface.depth(),
+            Aov::Position => surface.position(),
+            Aov::ObjectId => Vec3::ONE * surface.object_id as f64,
+            Aov::MaterialId => Vec3::ONE * surface.material_id as f64,
+            Aov::SampleCount => Vec3::ONE * pixel.samples() as f64,
+            Aov::Error => Vec3::ONE * self.film.error(i, j),
+        }
+    }
+}
+
+// Values mapped into [0, 1], meant for LDR formats
+// Depth and position are normalized over the image, ids get a random color
+// and the sample count and error go from blue (lowest) to red (highest)
+pub struct AovPreview<'a> {
+    view: AovView<'a>,
+    min: Vec3,
+    max: Vec3,
+}
+
+impl<'a> AovPreview<'a> {
+    pub fn new(film: &'a Film, aov: Aov) -> Self {
+        let view = AovView::new(film, aov);
+        let mut min = Vec3::ONE * INFINITY;
+        let mut max = Vec3::ONE * -INFINITY;
+        for j in 0..film.resolution.height {
+            for i in 0..film.resolution.width {
+                // pixels without a hit would drag the range to 0,
+                // and the ones outside the window were never rendered
+                let everywhere = matches!(aov, Aov::SampleCount | Aov::Error);
+                if !film.window().contains(i, j)
+                    || (!everywhere && film.get(i, j).surface.hits == 0)
+                {
+                    continue;
+                }
+                let value = view.pixel(i, j);
+                min = Vec3::new(min.x.min(value.x), min.y.min(value.y), min.z.min(value.z));
+                max = Vec3::new(max.x.max(value.x), max.y.max(value.y), max.z.max(value.z));
+            }
+        }
+        Self { view, min, max }
+    }
+
+    // Position of value in [min, max], per channel
+    fn normalize(&self, value: Vec3) -> Vec3 {
+        let unit = |x: f64, min: f64, max: f64| {
+            if max > min {
+                Interval::UNIT.clamp((x - min) / (max - min))
+            } else {
+                0.0
+            }
+        };
+        Vec3::new(
+            unit(value.x, self.min.x, self.max.x),
+            unit(value.y, self.min.y, self.max.y),
+            unit(value.z, self.min.z, self.max.z),
+        )
+    }
+}
+
+impl Image for AovPreview<'_> {
+    fn resolution(&self) -> Resolution {
+        self.view.resolution()
+    }
+
+    fn pixel(&self, i: u32, j: u32) -> Color {
+        let value = self.view.pixel(i, j);
+        match self.view.aov {
+            Aov::Albedo => Color::new(
+                linear_to_srgb(value.x),
+                linear_to_srgb(value.y),
+                linear_to_srgb(value.z),
+            ),
+            Aov::Normal => 0.5 * (value + Vec3::ONE),
+            Aov::Depth | Aov::Position => self.normalize(value),
+            Aov::ObjectId | Aov::MaterialId => id_color(value.x as u32),
+            Aov::SampleCount | Aov::Error => get_heatmap_color(self.normalize(value).x),
+        }
+    }
+}
+
+// Stable, well spread color for an id, black for 0
+pub(crate) fn id_color(id: u32) -> Color {
+    if id == 0 {
+        return Color::ZERO;
+    }
+    let bits = mix64(id as u64);
+    let channel = |shift: u32| 0.2 + 0.8 * ((bits >> shift) & 0xff) as f64 / 255.0;
+    Color::new(channel(0), channel(8), channel(16))
+}
@@ -4,18 +4,24 @@ use crate::prelude::*;
     This file contains
         1. Camera
         2. Resoltion
+        3. Shutter
 */
 
+#[derive(Clone)]
 pub struct Camera {
     pub resolution: Resolution,
 
     center: Point3,
+    // unit vector the camera looks along
+    forward: Vec3,
     defocus_disk_u: Vec3,
     defocus_disk_v: Vec3,
 
     pixel00_loc: Point3,
     pixel_delta_u: Vec3,
     pixel_delta_v: Vec3,
+
+    shutter: Shutter,
 }
 
 impl Camera {
@@ -50,21 +56,33 @@ impl Camera {
             resolution,
 
             center,
+            forward: -w,
             defocus_disk_u,
             defocus_disk_v,
 
             pixel00_loc,
             pixel_delta_u,
             pixel_delta_v,
+
+            shutter: Shutter::default(),
         }
     }
 
-    pub fn sample_ray(&self, i: u32, j: u32, time: f64) -> Ray {
-        let offset = sample_square();
-        let pixel_sample = self.pixel00_loc
-            + (i as f64 + offset.x) * self.pixel_delta_u
-            + (j as f64 + offset.y) * self.pixel_delta_v;
-        let defocus_disk_sample = self.sample_defocus_disk();
+    // Same camera, exposing the film while the shutter is open
+    pub fn with_shutter(mut self, shutter: Shutter) -> Self {
+        self.shutter = shutter;
+        self
+    }
+
+    pub fn shutter(&self) -> Shutter {
+        self.shutter
+    }
+
+    // Ray through film position (x, y), pixel (i, j) covers [i, i + 1) x [j, j + 1)
+    pub fn ray_at(&self, x: f64, y: f64, time: f64, rng: &mut Rng) -> Ray {
+        let pixel_sample =
+            self.pixel00_loc + (x - 0.5) * self.pixel_delta_u + (y - 0.5) * self.pixel_delta_v;
+        let defocus_disk_sample = self.sample_defocus_disk(rng);
         // Monte Carlo
         Ray::with_time(
             defocus_disk_sample,
@@ -73,8 +91,13 @@ impl Camera {
         )
     }
 
-    fn sample_defocus_disk(&self) -> Point3 {
-        let p = sample_in_unit_disk();
+    // Distance of the point from the camera along the view direction
+    pub fn depth(&self, point: Point3) -> f64 {
+        (point - self.center).dot(self.forward)
+    }
+
+    fn sample_defocus_disk(&self, rng: &mut Rng) -> Point3 {
+        let p = sample_in_unit_disk(rng);
         self.center + p.x * self.defocus_disk_u + p.y * self.defocus_disk_v
     }
 }
@@ -113,6 +136,41 @@ impl Default for Resolution {
     }
 }
 
+// Stretch of the scene's timeline a frame sees, motion in between is blurred
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Shutter {
+    pub open: f64,
+    pub close: f64,
+}
+
+impl Shutter {
+    pub fn new(open: f64, close: f64) -> Self {
+        Self { open, close }
+    }
+
+    // Shutter of a camera running at `fps`, open for `angle` degrees of the
+    // 360 a frame lasts, starting at `frame_time`
+    pub fn from_angle(frame_time: f64, fps: f64, angle: f64) -> Self {
+        Self::new(frame_time, frame_time + angle / 360.0 / fps)
+    }
+
+    pub fn duration(&self) -> f64 {
+        self.close - self.open
+    }
+
+    // Time of a sample at fraction u in [0, 1] of the exposure
+    pub fn time_at(&self, u: f64) -> f64 {
+        self.open + u * self.duration()
+    }
+}
+
+// Open from 0 to 1, what motion instances move over by default
+impl Default for Shutter {
+    fn default() -> Self {
+        Self::new(0.0, 1.0)
+    }
+}
+
 #[derive(Clone, Copy)]
 pub struct CameraSettings {
     pub vertical_fov: f64,
@@ -140,15 +198,7 @@ impl Default for CameraSettings {
     }
 }
 
-fn sample_square() -> Vec3 {
-    Vec3::new(random_unit_f64() - 0.5, random_unit_f64() - 0.5, 0.0)
-}
-
-fn sample_in_unit_disk() -> Vec3 {
-    use rand::random_range;
-    let r = random_unit_f64().sqrt();
-    let theta = random_range(0.0..(2.0 * PI));
-    let sin_theta = theta.sin();
-    let cos_theta = theta.cos();
-    Vec3::new(r * cos_theta, r * sin_theta, 0.0)
+fn sample_in_unit_disk(rng: &mut Rng) -> Vec3 {
+    let (x, y) = sample_concentric_disk(rng.random_2d());
+    Vec3::new(x, y, 0.0)
 }
@@ -0,0 +1,260 @@
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Error, ErrorKind, Read, Write};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::prelude::*;
+use crate::render::{
+    CropWindow, Film, Pixel, Resolution, RunningStats, Shutter, Splat, SurfaceStats,
+};
+
+/*
+    Periodic snapshot of a tiled render
+
+    Layout (little-endian):
+        magic, width, height, crop window (x0, y0, x1, y1), shutter (open, close),
+        tile size, seed, settings fingerprint,
+        one byte per tile (1 = finished, in scanline tile order),
+        then per pixel: filtered color and weight sums (fixed point),
+        sample count, luminance mean and m2,
+        even and odd color sums, converged flag,
+        first-hit count, albedo, normal, position and depth sums, object and material id
+
+    The fingerprint stands for the settings that change what a saved pixel
+    means (integrator, sampler, filter, adaptive sampling), see fingerprint.
+    Only finished tiles are trusted on resume, the rest are rendered again.
+    The file is removed once the render completes.
+*/
+
+const MAGIC: &[u8; 8] = b"RTCKPT07";
+
+#[derive(Clone)]
+pub struct Checkpoint {
+    pub path: PathBuf,
+    // Minimum time between two saves
+    pub interval: Duration,
+}
+
+impl Checkpoint {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            interval: Duration::from_secs(30),
+        }
+    }
+
+    pub fn with_interval(path: impl Into<PathBuf>, interval: Duration) -> Self {
+        Self {
+            path: path.into(),
+            interval,
+        }
+    }
+
+    pub fn save(
+        &self,
+        film: &Film,
+        done: &[bool],
+        shutter: Shutter,
+        tile_size: u32,
+        seed: u64,
+        settings: u64,
+    ) -> std::io::Result<()> {
+        // write next to the target and rename, so a crash never leaves a torn file
+        // the whole file name gets the suffix, with_extension would map a.b and a.c to one file
+        let mut temporary = self.path.clone().into_os_string();
+        temporary.push(".partial");
+        let temporary = PathBuf::from(temporary);
+        let mut writer = BufWriter::new(File::create(&temporary)?);
+
+        writer.write_all(MAGIC)?;
+        writer.write_all(&film.resolution.width.to_le_bytes())?;
+        writer.write_all(&film.resolution.height.to_le_bytes())?;
+        let window = film.window();
+        for bound in [window.x0, window.y0, window.x1, window.y1] {
+            writer.write_all(&bound.to_le_bytes())?;
+        }
+        writer.write_all(&shutter.open.to_le_bytes())?;
+        writer.write_all(&shutter.close.to_le_bytes())?;
+        writer.write_all(&tile_size.to_le_bytes())?;
+        writer.write_all(&seed.to_le_bytes())?;
+        writer.write_all(&settings.to_le_bytes())?;
+
+        writer.write_all(&(done.len() as u32).to_le_bytes())?;
+        for &finished in done {
+            writer.write_all(&[finished as u8])?;
+        }
+
+        for (pixel, splat) in film.pixels().iter().zip(film.splats()) {
+            for value in splat.to_bits() {
+                writer.write_all(&value.to_le_bytes())?;
+            }
+            writer.write_all(&pixel.luminance.n.to_le_bytes())?;
+            writer.write_all(&pixel.luminance.mean.to_le_bytes())?;
+            writer.write_all(&pixel.luminance.m2.to_le_bytes())?;
+            write_vec3(&mut writer, pixel.halves[0])?;
+            write_vec3(&mut writer, pixel.halves[1])?;
+            writer.write_all(&[pixel.converged as u8])?;
+
+            let surface = &pixel.surface;
+            writer.write_all(&surface.hits.to_le_bytes())?;
+            write_vec3(&mut writer, surface.albedo_sum)?;
+            write_vec3(&mut writer, surface.normal_sum)?;
+            write_vec3(&mut writer, surface.position_sum)?;
+            writer.write_all(&surface.depth_sum.to_le_bytes())?;
+            writer.write_all(&surface.object_id.to_le_bytes())?;
+            writer.write_all(&surface.material_id.to_le_bytes())?;
+        }
+
+        writer.into_inner()?.sync_all()?;
+        std::fs::rename(temporary, &self.path)
+    }
+
+    // Returns None when there is nothing to resume from
+    pub fn load(
+        &self,
+        resolution: Resolution,
+        window: CropWindow,
+        shutter: Shutter,
+        tile_size: u32,
+        seed: u64,
+        settings: u64,
+    ) -> std::io::Result<Option<(Film, Vec<bool>)>> {
+        let file = match File::open(&self.path) {
+            Ok(file) => file,
+            Err(error) if error.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(error) => return Err(error),
+        };
+        let mut reader = BufReader::new(file);
+
+        let mut magic = [0u8; 8];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid("not a render checkpoint"));
+        }
+
+        let width = read_u32(&mut reader)?;
+        let height = read_u32(&mut reader)?;
+        let saved_window = CropWindow::new(
+            read_u32(&mut reader)?,
+            read_u32(&mut reader)?,
+            read_u32(&mut reader)?,
+            read_u32(&mut reader)?,
+        );
+        let saved_shutter = Shutter::new(read_f64(&mut reader)?, read_f64(&mut reader)?);
+        let saved_tile_size = read_u32(&mut reader)?;
+        let saved_seed = read_u64(&mut reader)?;
+        let saved_settings = read_u64(&mut reader)?;
+        if width != resolution.width
+            || height != resolution.height
+            || saved_window != window.clamp(resolution)
+            || saved_shutter != shutter
+            || saved_tile_size != tile_size
+            || saved_seed != seed
+        {
+            return Err(invalid(
+                "checkpoint was made with a different resolution, crop window, shutter, tile size or seed",
+            ));
+        }
+        if saved_settings != settings {
+            return Err(invalid(
+                "checkpoint was made with a different integrator, sampler, filter or adaptive sampling",
+            ));
+        }
+
+        let tile_count = read_u32(&mut reader)? as usize;
+        let mut flags = vec![0u8; tile_count];
+        reader.read_exact(&mut flags)?;
+        let done = flags.into_iter().map(|flag| flag != 0).collect();
+
+        let mut pixels = Vec::with_capacity((width * height) as usize);
+        let mut splats = Vec::with_capacity((width * height) as usize);
+        for _ in 0..width * height {
+            let mut bits = [0i128; 4];
+            for value in &mut bits {
+                let mut bytes = [0u8; 16];
+                reader.read_exact(&mut bytes)?;
+                *value = i128::from_le_bytes(bytes);
+            }
+            splats.push(Splat::from_bits(bits));
+
+            let luminance = RunningStats {
+                n: read_u32(&mut reader)?,
+                mean: read_f64(&mut reader)?,
+                m2: read_f64(&mut reader)?,
+            };
+            let halves = [read_vec3(&mut reader)?, read_vec3(&mut reader)?];
+            let mut converged = [0u8];
+            reader.read_exact(&mut converged)?;
+            let surface = SurfaceStats {
+                hits: read_u32(&mut reader)?,
+                albedo_sum: read_vec3(&mut reader)?,
+                normal_sum: read_vec3(&mut reader)?,
+                position_sum: read_vec3(&mut reader)?,
+                depth_sum: read_f64(&mut reader)?,
+                object_id: read_u32(&mut reader)?,
+                material_id: read_u32(&mut reader)?,
+            };
+            pixels.push(Pixel {
+                luminance,
+                halves,
+                converged: converged[0] != 0,
+                surface,
+            });
+        }
+
+        let mut film = Film::from_pixels(resolution, pixels, splats);
+        film.set_window(window);
+        Ok(Some((film, done)))
+    }
+
+    pub fn remove(&self) -> std::io::Result<()> {
+        match std::fs::remove_file(&self.path) {
+            Err(error) if error.kind() != ErrorKind::NotFound => Err(error),
+            _ => Ok(()),
+        }
+    }
+}
+
+// FNV-1a of a description of the settings, stable across runs and builds
+pub(crate) fn fingerprint(settings: &str) -> u64 {
+    settings.bytes().fold(0xCBF2_9CE4_8422_2325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01B3)
+    })
+}
+
+fn invalid(message: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, message.to_string())
+}
+
+fn read_u32(reader: &mut impl Read) -> std::io::Result<u32> {
+    let mut bytes = [0u8; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_u64(reader: &mut impl Read) -> std::io::Result<u64> {
+    let mut bytes = [0u8; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+fn read_f64(reader: &mut impl Read) -> std::io::Result<f64> {
+    let mut bytes = [0u8; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(f64::from_le_bytes(bytes))
+}
+
+fn read_vec3(reader: &mut impl Read) -> std::io::Result<Vec3> {
+    Ok(Vec3::new(
+        read_f64(reader)?,
+        read_f64(reader)?,
+        read_f64(reader)?,
+    ))
+}
+
+fn write_vec3(writer: &mut impl Write, v: Vec3) -> std::io::Result<()> {
+    for value in [v.x, v.y, v.z] {
+        writer.write_all(&value.to_le_bytes())?;
+    }
+    Ok(())
+}
@@ -0,0 +1,140 @@
+use crate::prelude::*;
+use crate::render::{Image, Resolution};
+
+/*
+    This file contains
+        1. CropWindow (rectangle of the image that gets rendered)
+        2. CropOutput (what written images cover)
+        3. Cropped / Composited (views of images around a window)
+*/
+
+// Pixels [x0, x1) x [y0, y1) of the camera's image
+// Tiles keep the grid of the full image, so the pixels inside come out the
+// same as in a full render, except at the edges: filters wider than a pixel
+// and the neighbour check of adaptive sampling only see the window
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CropWindow {
+    pub x0: u32,
+    pub y0: u32,
+    pub x1: u32,
+    pub y1: u32,
+}
+
+impl CropWindow {
+    pub fn new(x0: u32, y0: u32, x1: u32, y1: u32) -> Self {
+        Self { x0, y0, x1, y1 }
+    }
+
+    pub fn full(resolution: Resolution) -> Self {
+        Self::new(0, 0, resolution.width, resolution.height)
+    }
+
+    // Same window given as fractions of the image, e.g. (0.5, 0.5, 1.0, 1.0) for the bottom right
+    pub fn from_fractions(resolution: Resolution, x0: f64, y0: f64, x1: f64, y1: f64) -> Self {
+        let column = |x: f64| (x.clamp(0.0, 1.0) * resolution.width as f64).round() as u32;
+        let row = |y: f64| (y.clamp(0.0, 1.0) * resolution.height as f64).round() as u32;
+        Self::new(column(x0), row(y0), column(x1), row(y1))
+    }
+
+    // The part of the window inside the image
+    pub fn clamp(&self, resolution: Resolution) -> Self {
+        let x1 = self.x1.min(resolution.width);
+        let y1 = self.y1.min(resolution.height);
+        Self::new(self.x0.min(x1), self.y0.min(y1), x1, y1)
+    }
+
+    pub fn width(&self) -> u32 {
+        self.x1.saturating_sub(self.x0)
+    }
+
+    pub fn height(&self) -> u32 {
+        self.y1.saturating_sub(self.y0)
+    }
+
+    pub fn area(&self) -> u32 {
+        self.width() * self.height()
+    }
+
+    pub fn resolution(&self) -> Resolution {
+        Resolution {
+            width: self.width(),
+            height: self.height(),
+        }
+    }
+
+    pub fn contains(&self, i: u32, j: u32) -> bool {
+        (self.x0..self.x1).contains(&i) && (self.y0..self.y1).contains(&j)
+    }
+
+    // Pixels of the window in scanline order
+    pub fn pixels(&self) -> impl Iterator<Item = (u32, u32)> + '_ {
+        (self.y0..self.y1).flat_map(move |j| (self.x0..self.x1).map(move |i| (i, j)))
+    }
+
+    // Only the window of a full-size image
+    pub fn crop<'a, T: Image>(&self, image: &'a T) -> Cropped<'a, T> {
+        Cropped {
+            image,
+            window: *self,
+        }
+    }
+
+    // Full-size image taking the window from `inside` and the rest from `outside`,
+    // e.g. a crop render over an earlier full render
+    pub fn composite<'a, A: Image, B: Image>(
+        &self,
+        inside: &'a A,
+        outside: &'a B,
+    ) -> Composited<'a, A, B> {
+        Composited {
+            inside,
+            outside,
+            window: *self,
+        }
+    }
+}
+
+// What the renderer writes when it has a crop window
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CropOutput {
+    // Images the size of the window
+    #[default]
+    Cropped,
+    // Images the size of the camera's, black outside the window
+    FullFrame,
+}
+
+pub struct Cropped<'a, T> {
+    image: &'a T,
+    window: CropWindow,
+}
+
+impl<T: Image> Image for Cropped<'_, T> {
+    fn resolution(&self) -> Resolution {
+        self.window.resolution()
+    }
+
+    fn pixel(&self, i: u32, j: u32) -> Color {
+        self.image.pixel(self.window.x0 + i, self.window.y0 + j)
+    }
+}
+
+pub struct Composited<'a, A, B> {
+    inside: &'a A,
+    outside: &'a B,
+    window: CropWindow,
+}
+
+impl<A: Image, B: Image> Image for Composited<'_, A, B> {
+    fn resolution(&self) -> Resolution {
+        self.outside.resolution()
+    }
+
+    fn pixel(&self, i: u32, j: u32) -> Color {
+        if self.window.contains(i, j) {
+            self.inside.pixel(i, j)
+        } else {
+            self.outside.pixel(i, j)
+        }
+    }
+}
@@ -0,0 +1,222 @@
+use rayon::prelude::*;
+
+use crate::prelude::*;
+use crate::render::{Film, ImageBuffer, Resolution};
+
+/*
+    Edge-avoiding A-trous wavelet filter (Dammertz et al. 2010)
+
+    The image is blurred with a 5x5 B3-spline kernel whose taps are spread
+    2^k pixels apart on iteration k, so a few iterations cover a large area.
+    Every tap is weighted down when its normal, depth or luminance differs
+    from the center pixel, which keeps edges and shadows sharp.
+
+    As in SVGF (Schied et al. 2017) the luminance difference is measured in
+    standard errors of the pixel, so noise gets blurred and real detail does
+    not. The variance is filtered along with the image.
+
+    The filter runs on the color divided by the albedo, so textures are not
+    blurred away, and the albedo is multiplied back in at the end.
+*/
+
+#[derive(Clone, Copy, Default)]
+pub enum Denoiser {
+    // Keep the noisy image
+    #[default]
+    Off,
+    ATrous {
+        iterations: u32,
+        // Larger values blur across bigger differences
+        // in standard errors of the pixel
+        sigma_luminance: f64,
+        sigma_normal: f64,
+        // relative to the depth of the center pixel
+        sigma_depth: f64,
+    },
+}
+
+impl Denoiser {
+    pub const ATROUS: Denoiser = Denoiser::ATrous {
+        iterations: 5,
+        sigma_luminance: 8.0,
+        sigma_normal: 0.2,
+        sigma_depth: 0.1,
+    };
+
+    // Denoised mean colors of the film, the film itself is left untouched
+    pub fn apply(&self, film: &Film) -> ImageBuffer {
+        let image = ImageBuffer::from_image(film);
+        match *self {
+            Denoiser::Off => image,
+            Denoiser::ATrous {
+                iterations,
+                sigma_luminance,
+                sigma_normal,
+                sigma_depth,
+            } => {
+                let guide = Guide::new(film);
+                let mut irradiance = guide.demodulate(&image);
+                let mut variance = guide.demodulate_variance(film);
+                for k in 0..iterations {
+                    (irradiance, variance) = guide.a_trous_pass(
+                        &irradiance,
+                        &variance,
+                        1 << k,
+                        sigma_luminance,
+                        sigma_normal,
+                        sigma_depth,
+                    );
+                }
+                guide.remodulate(&irradiance)
+            }
+        }
+    }
+}
+
+const KERNEL: [f64; 5] = [1.0 / 16.0, 1.0 / 4.0, 3.0 / 8.0, 1.0 / 4.0, 1.0 / 16.0];
+
+// Albedo below this is not divided out, it would only amplify the noise
+const MIN_ALBEDO: f64 = 0.01;
+
+// Per-pixel first-hit data of the film
+struct Guide {
+    width: u32,
+    height: u32,
+    albedo: Vec<Color>,
+    normal: Vec<Vec3>,
+    depth: Vec<f64>,
+}
+
+impl Guide {
+    fn new(film: &Film) -> Self {
+        let surfaces = film.pixels().iter().map(|p| p.surface);
+        Self {
+            width: film.resolution.width,
+            height: film.resolution.height,
+            albedo: surfaces.clone().map(|s| s.albedo()).collect(),
+            normal: surfaces.clone().map(|s| s.normal()).collect(),
+            depth: surfaces.map(|s| s.depth()).collect(),
+        }
+    }
+
+    fn demodulate(&self, image: &ImageBuffer) -> Vec<Color> {
+        image
+            .pixels()
+            .iter()
+            .zip(&self.albedo)
+            .map(|(&color, &albedo)| color / albedo.map(|a| a.max(MIN_ALBEDO)))
+            .collect()
+    }
+
+    // Variance of the mean luminance of each pixel, in the units of demodulate
+    fn demodulate_variance(&self, film: &Film) -> Vec<f64> {
+        film.pixels()
+            .iter()
+            .zip(&self.albedo)
+            .map(|(pixel, &albedo)| {
+                let samples = pixel.samples().max(1) as f64;
+                let scale = luminance(albedo.map(|a| a.max(MIN_ALBEDO)));
+                pixel.luminance.variance() / samples / (scale * scale)
+            })
+            .collect()
+    }
+
+    // 3x3 Gaussian of the variance around (i, j)
+    // A single pixel's estimate is unreliable, e.g. zero when all its samples were black
+    fn blurred_variance(&self, variance: &[f64], i: i64, j: i64) -> f64 {
+        let (width, height) = (self.width as i64, self.height as i64);
+        let mut sum = 0.0;
+        let mut total_weight = 0.0;
+        for (dy, ky) in [0.25, 0.5, 0.25].iter().enumerate() {
+            for (dx, kx) in [0.25, 0.5, 0.25].iter().enumerate() {
+                let x = i + dx as i64 - 1;
+                let y = j + dy as i64 - 1;
+                if (0..width).contains(&x) && (0..height).contains(&y) {
+                    sum += kx * ky * variance[(y * width + x) as usize];
+                    total_weight += kx * ky;
+                }
+            }
+        }
+        sum / total_weight
+    }
+
+    fn remodulate(&self, irradiance: &[Color]) -> ImageBuffer {
+        let mut image = ImageBuffer::new(Resolution {
+            width: self.width,
+            height: self.height,
+        });
+        for ((out, &e), &albedo) in image
+            .pixels_mut()
+            .iter_mut()
+            .zip(irradiance)
+            .zip(&self.albedo)
+        {
+            *out = e * albedo.map(|a| a.max(MIN_ALBEDO));
+        }
+        image
+    }
+
+    fn a_trous_pass(
+        &self,
+        input: &[Color],
+        variance: &[f64],
+        step: i64,
+        sigma_luminance: f64,
+        sigma_normal: f64,
+        sigma_depth: f64,
+    ) -> (Vec<Color>, Vec<f64>) {
+        let (width, height) = (self.width as i64, self.height as i64);
+        let mut output = vec![(Color::ZERO, 0.0); input.len()];
+
+        output
+            .par_chunks_mut(self.width.max(1) as usize)
+            .enumerate()
+            .for_each(|(j, row)| {
+                let j = j as i64;
+                for (i, out) in row.iter_mut().enumerate() {
+                    let i = i as i64;
+                    let p = (j * width + i) as usize;
+                    let luminance_p = luminance(input[p]);
+                    let luminance_scale =
+                        sigma_luminance * self.blurred_variance(variance, i, j).sqrt() + 1e-6;
+
+                    let mut sum = Color::ZERO;
+                    let mut variance_sum = 0.0;
+                    let mut total_weight = 0.0;
+                    for (dy, ky) in KERNEL.iter().enumerate() {
+                        for (dx, kx) in KERNEL.iter().enumerate() {
+                            let x = i + (dx as i64 - 2) * step;
+                            let y = j + (dy as i64 - 2) * step;
+                            if !(0..width).contains(&x) || !(0..height).contains(&y) {
+                                continue;
+                            }
+                            let q = (y * width + x) as usize;
+
+                            let luminance_distance =
+                                (luminance(input[q]) - luminance_p).abs() / luminance_scale;
+                            let normal_distance =
+                                (self.normal[q] - self.normal[p]).length_squared();
+                            let depth_distance = (self.depth[q] - self.depth[p]).abs()
+                                / (sigma_depth * self.depth[p].max(0.001));
+
+                            let weight = kx
+                                * ky
+                                * (-luminance_distance
+                                    - normal_distance / (sigma_normal * sigma_normal)
+                                    - depth_distance * depth_distance)
+                                    .exp();
+                            sum += weight * input[q];
+                            variance_sum += weight * weight * variance[q];
+                            total_weight += weight;
+                        }
+                    }
+                    // the center tap always has a weight of KERNEL[2]^2
+                    *out = (
+                        sum / total_weight,
+                        variance_sum / (total_weight * total_weight),
+                    );
+                }
+            });
+        output.into_iter().unzip()
+    }
+}
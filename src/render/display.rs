@@ -0,0 +1,149 @@
+use crate::prelude::*;
+use crate::render::{Image, Resolution};
+
+/*
+    Display transform for LDR output
+        1. exposure (in stops)
+        2. tone mapping operator (scene-linear -> display-linear)
+        3. sRGB OETF (display-linear -> encoded)
+
+    HDR formats skip this stage and store the film as is.
+*/
+
+#[derive(Clone, Copy)]
+pub enum ToneMap {
+    // Hard clip at 1.0
+    Clamp,
+    // L / (1 + L) on luminance
+    Reinhard,
+    // Reinhard that maps luminance `white` to 1.0 instead of infinity
+    ExtendedReinhard { white: f64 },
+    // Stephen Hill's fit of the ACES RRT + sRGB ODT
+    AcesFitted,
+    // Troy Sobotka's AgX with the default contrast curve
+    Agx,
+}
+
+#[derive(Clone, Copy)]
+pub struct DisplayTransform {
+    pub exposure: f64,
+    pub tone_map: ToneMap,
+}
+
+impl DisplayTransform {
+    pub fn new(exposure: f64, tone_map: ToneMap) -> Self {
+        Self { exposure, tone_map }
+    }
+
+    // Returns the sRGB-encoded color, each channel in [0, 1]
+    pub fn apply(&self, linear: Color) -> Color {
+        let exposed = linear * 2.0_f64.powf(self.exposure);
+        let mapped = match self.tone_map {
+            ToneMap::Clamp => exposed,
+            ToneMap::Reinhard => scale_luminance(exposed, |l| l / (1.0 + l)),
+            ToneMap::ExtendedReinhard { white } => {
+                scale_luminance(exposed, |l| l * (1.0 + l / (white * white)) / (1.0 + l))
+            }
+            ToneMap::AcesFitted => aces_fitted(exposed),
+            ToneMap::Agx => agx(exposed),
+        };
+
+        Color::new(
+            linear_to_srgb(Interval::UNIT.clamp(mapped.x)),
+            linear_to_srgb(Interval::UNIT.clamp(mapped.y)),
+            linear_to_srgb(Interval::UNIT.clamp(mapped.z)),
+        )
+    }
+
+    pub fn view<'a, I: Image>(&self, image: &'a I) -> Displayed<'a, I> {
+        Displayed {
+            image,
+            transform: *self,
+        }
+    }
+}
+
+impl Default for DisplayTransform {
+    fn default() -> Self {
+        Self::new(0.0, ToneMap::Clamp)
+    }
+}
+
+// An image with the display transform applied to every pixel
+pub struct Displayed<'a, I> {
+    image: &'a I,
+    transform: DisplayTransform,
+}
+
+impl<I: Image> Image for Displayed<'_, I> {
+    fn resolution(&self) -> Resolution {
+        self.image.resolution()
+    }
+
+    fn pixel(&self, i: u32, j: u32) -> Color {
+        self.transform.apply(self.image.pixel(i, j))
+    }
+}
+
+fn scale_luminance(color: Color, curve: impl Fn(f64) -> f64) -> Color {
+    let l = luminance(color);
+    if l <= 0.0 {
+        return Color::ZERO;
+    }
+    color * (curve(l) / l)
+}
+
+const ACES_INPUT: Mat3 = Mat3::new([
+    [0.59719, 0.35458, 0.04823],
+    [0.07600, 0.90834, 0.01566],
+    [0.02840, 0.13383, 0.83777],
+]);
+
+const ACES_OUTPUT: Mat3 = Mat3::new([
+    [1.60475, -0.53108, -0.07367],
+    [-0.10208, 1.10813, -0.00605],
+    [-0.00327, -0.07276, 1.07602],
+]);
+
+fn aces_fitted(color: Color) -> Color {
+    let v = &ACES_INPUT * color;
+    let rrt_odt =
+        |x: f64| (x * (x + 0.0245786) - 0.000090537) / (x * (0.983729 * x + 0.4329510) + 0.238081);
+    &ACES_OUTPUT * Color::new(rrt_odt(v.x), rrt_odt(v.y), rrt_odt(v.z))
+}
+
+const AGX_INSET: Mat3 = Mat3::new([
+    [0.842479062253094, 0.0784335999999992, 0.0792237451477643],
+    [0.0423282422610123, 0.878468636469772, 0.0791661274605434],
+    [0.0423756549057051, 0.0784336, 0.879142973793104],
+]);
+
+const AGX_OUTSET: Mat3 = Mat3::new([
+    [1.19687900512017, -0.0980208811401368, -0.0990297440797205],
+    [-0.0528968517574562, 1.15190312990417, -0.0989611768448433],
+    [-0.0529716355144438, -0.0980434501171241, 1.15107367264116],
+]);
+
+fn agx(color: Color) -> Color {
+    const MIN_EV: f64 = -12.47393;
+    const MAX_EV: f64 = 4.026069;
+
+    // log2 encoding into [0, 1], then the sigmoid fitted as a polynomial
+    let encode = |x: f64| {
+        let ev = x.max(1e-10).log2().clamp(MIN_EV, MAX_EV);
+        let x = (ev - MIN_EV) / (MAX_EV - MIN_EV);
+        let x2 = x * x;
+        let x4 = x2 * x2;
+        15.5 * x4 * x2 - 40.14 * x4 * x + 31.96 * x4 - 6.868 * x2 * x + 0.4298 * x2 + 0.1191 * x
+            - 0.00232
+    };
+
+    let v = &AGX_INSET * color;
+    let v = &AGX_OUTSET * Color::new(encode(v.x), encode(v.y), encode(v.z));
+    // the curve output is gamma 2.2 encoded
+    Color::new(
+        v.x.max(0.0).powf(2.2),
+        v.y.max(0.0).powf(2.2),
+        v.z.max(0.0).powf(2.2),
+    )
+}
@@ -0,0 +1,493 @@
+use crate::prelude::*;
+use crate::render::{
+    Aov, AovPreview, AovView, CropWindow, Filter, Resolution, Surface, SurfaceStats, Tile,
+};
+
+/*
+    This file contains
+        1. Image (anything a writer can consume)
+        2. Film (in-memory framebuffer produced by the renderer)
+        3. Pixel (sampling state of one pixel)
+        4. Splat / SplatBuffer (filter-weighted colors, see filter.rs)
+        5. ImageBuffer (plain owned image, e.g. a post-processed film)
+*/
+
+pub trait Image {
+    fn resolution(&self) -> Resolution;
+    // Linear RGB of pixel (i, j), i = column, j = row from the top
+    fn pixel(&self, i: u32, j: u32) -> Color;
+}
+
+#[derive(Clone)]
+pub struct Film {
+    pub resolution: Resolution,
+    // the pixels that get rendered, the others stay black without samples
+    window: CropWindow,
+    pixels: Vec<Pixel>,
+    // the color of each pixel, gathered from the samples around it
+    splats: Vec<Splat>,
+}
+
+impl Film {
+    pub fn new(resolution: Resolution) -> Self {
+        Self::with_window(resolution, CropWindow::full(resolution))
+    }
+
+    // Full-size film of which only `window` is rendered
+    pub fn with_window(resolution: Resolution, window: CropWindow) -> Self {
+        let size = (resolution.width * resolution.height) as usize;
+        Self {
+            resolution,
+            window: window.clamp(resolution),
+            pixels: vec![Pixel::new(); size],
+            splats: vec![Splat::new(); size],
+        }
+    }
+
+    // Builds a film from pixels and splats given in scanline order
+    pub fn from_pixels(resolution: Resolution, pixels: Vec<Pixel>, splats: Vec<Splat>) -> Self {
+        let size = (resolution.width * resolution.height) as usize;
+        assert!(
+            pixels.len() == size && splats.len() == size,
+            "pixel count does not match the resolution"
+        );
+        Self {
+            resolution,
+            window: CropWindow::full(resolution),
+            pixels,
+            splats,
+        }
+    }
+
+    pub fn window(&self) -> CropWindow {
+        self.window
+    }
+
+    pub fn set_window(&mut self, window: CropWindow) {
+        self.window = window.clamp(self.resolution);
+    }
+
+    // Pixels of the window in scanline order
+    pub fn window_pixels(&self) -> impl Iterator<Item = &Pixel> + '_ {
+        self.window.pixels().map(|(i, j)| self.get(i, j))
+    }
+
+    fn index(&self, i: u32, j: u32) -> usize {
+        (j * self.resolution.width + i) as usize
+    }
+
+    pub fn get(&self, i: u32, j: u32) -> &Pixel {
+        &self.pixels[self.index(i, j)]
+    }
+
+    pub fn set(&mut self, i: u32, j: u32, pixel: Pixel) {
+        let index = self.index(i, j);
+        self.pixels[index] = pixel;
+    }
+
+    // All pixels in scanline order
+    pub fn pixels(&self) -> &[Pixel] {
+        &self.pixels
+    }
+
+    pub fn pixels_mut(&mut self) -> &mut [Pixel] {
+        &mut self.pixels
+    }
+
+    // Filtered colors in scanline order
+    pub fn splats(&self) -> &[Splat] {
+        &self.splats
+    }
+
+    // Current state of the pixels of a tile, in scanline order
+    pub fn tile_pixels(&self, tile: &Tile) -> Vec<Pixel> {
+        tile.pixels().map(|(i, j)| *self.get(i, j)).collect()
+    }
+
+    // Filtered colors of a tile's pixels as they are now, in scanline order
+    // Tiles finished later may still splat into them
+    pub fn tile_colors(&self, tile: &Tile) -> Vec<Color> {
+        tile.pixels().map(|(i, j)| self.pixel(i, j)).collect()
+    }
+
+    // Stores the new state of a tile's pixels and adds the colors its samples splatted
+    pub fn merge_tile(&mut self, tile: &Tile, pixels: Vec<Pixel>, splats: &SplatBuffer) {
+        for ((i, j), pixel) in tile.pixels().zip(pixels) {
+            self.set(i, j, pixel);
+        }
+        for j in splats.y0..splats.y1 {
+            for i in splats.x0..splats.x1 {
+                let index = self.index(i, j);
+                self.splats[index].merge(splats.get(i, j));
+            }
+        }
+    }
+
+    pub fn samples(&self, i: u32, j: u32) -> u32 {
+        self.get(i, j).samples()
+    }
+
+    // Over the pixels of the window
+    pub fn min_samples(&self) -> u32 {
+        self.window_pixels().map(Pixel::samples).min().unwrap_or(0)
+    }
+
+    pub fn max_samples(&self) -> u32 {
+        self.window_pixels().map(Pixel::samples).max().unwrap_or(0)
+    }
+
+    // Error estimate of the pixel averaged with its neighbours, see Pixel::error
+    // 0 outside the window
+    pub fn error(&self, i: u32, j: u32) -> f64 {
+        if !self.window.contains(i, j) {
+            return 0.0;
+        }
+        let (width, height) = (self.resolution.width, self.resolution.height);
+        neighbourhood_error(&self.pixels, width, height, i, j)
+    }
+
+    // Raw values of an auxiliary buffer
+    pub fn aov(&self, aov: Aov) -> AovView<'_> {
+        AovView::new(self, aov)
+    }
+
+    // Auxiliary buffer mapped into [0, 1], e.g. the sample count heatmap
+    pub fn aov_preview(&self, aov: Aov) -> AovPreview<'_> {
+        AovPreview::new(self, aov)
+    }
+}
+
+impl Image for Film {
+    fn resolution(&self) -> Resolution {
+        self.resolution
+    }
+
+    // Black outside the window, even where wide filters splatted across its edge
+    fn pixel(&self, i: u32, j: u32) -> Color {
+        if !self.window.contains(i, j) {
+            return Color::ZERO;
+        }
+        self.splats[self.index(i, j)].mean()
+    }
+}
+
+// Linear RGB values in scanline order
+#[derive(Clone)]
+pub struct ImageBuffer {
+    pub resolution: Resolution,
+    pixels: Vec<Color>,
+}
+
+impl ImageBuffer {
+    pub fn new(resolution: Resolution) -> Self {
+        let size = (resolution.width * resolution.height) as usize;
+        Self {
+            resolution,
+            pixels: vec![Color::ZERO; size],
+        }
+    }
+
+    // Copies any image, e.g. the mean colors of a film
+    pub fn from_image(image: &impl Image) -> Self {
+        let resolution = image.resolution();
+        let pixels = (0..resolution.height)
+            .flat_map(|j| (0..resolution.width).map(move |i| (i, j)))
+            .map(|(i, j)| image.pixel(i, j))
+            .collect();
+        Self { resolution, pixels }
+    }
+
+    pub fn get(&self, i: u32, j: u32) -> Color {
+        self.pixels[(j * self.resolution.width + i) as usize]
+    }
+
+    pub fn set(&mut self, i: u32, j: u32, color: Color) {
+        self.pixels[(j * self.resolution.width + i) as usize] = color;
+    }
+
+    pub fn pixels(&self) -> &[Color] {
+        &self.pixels
+    }
+
+    pub fn pixels_mut(&mut self) -> &mut [Color] {
+        &mut self.pixels
+    }
+}
+
+impl Image for ImageBuffer {
+    fn resolution(&self) -> Resolution {
+        self.resolution
+    }
+
+    fn pixel(&self, i: u32, j: u32) -> Color {
+        self.get(i, j)
+    }
+}
+
+// Samples taken in one pixel
+// Their colors go to the film's splats, this keeps what is needed to decide
+// whether to take more
+#[derive(Clone, Copy)]
+pub struct Pixel {
+    // statistics of the sample luminance, used by the denoiser and the noise estimate
+    pub luminance: RunningStats,
+    // color sums of the even and of the odd samples, used for adaptive sampling
+    pub halves: [Color; 2],
+    // set once the renderer decided the pixel needs no more samples
+    pub converged: bool,
+    pub surface: SurfaceStats,
+}
+
+impl Pixel {
+    pub const fn new() -> Self {
+        Self {
+            luminance: RunningStats::new(),
+            halves: [Color::ZERO; 2],
+            converged: false,
+            surface: SurfaceStats::new(),
+        }
+    }
+
+    // `surface` is what the camera ray hit first, if anything
+    pub fn add_sample(&mut self, color: Color, surface: Option<&Surface>) {
+        let half = (self.samples() % 2) as usize;
+        self.halves[half] += color;
+        self.luminance.add(luminance(color));
+        if let Some(surface) = surface {
+            self.surface.add(surface);
+        }
+    }
+
+    pub fn samples(&self) -> u32 {
+        self.luminance.n
+    }
+
+    pub fn mean(&self) -> Color {
+        match self.samples() {
+            0 => Color::ZERO,
+            n => (self.halves[0] + self.halves[1]) / n as f64,
+        }
+    }
+
+    /*
+        Two-buffer error estimate (Dammertz et al. 2010)
+
+        How far the mean of the even samples is from the mean of all of them,
+        summed over the channels and divided by the square root of the
+        brightness, so dark pixels are held to about the same visible noise
+        as bright ones. 0 until there are two samples.
+    */
+    pub fn error(&self) -> f64 {
+        let n = self.samples();
+        if n < 2 {
+            return 0.0;
+        }
+        let all = self.mean();
+        let even = self.halves[0] / n.div_ceil(2) as f64;
+        let difference = (all.x - even.x).abs() + (all.y - even.y).abs() + (all.z - even.z).abs();
+        difference / (all.x + all.y + all.z).max(1e-8).sqrt()
+    }
+}
+
+// Mean error of pixel (i, j) and the ones around it, among `width` x `height`
+// pixels in scanline order
+// A pixel that got lucky with its first samples still waits for noisy neighbours
+pub fn neighbourhood_error(pixels: &[Pixel], width: u32, height: u32, i: u32, j: u32) -> f64 {
+    let columns = i.saturating_sub(1)..(i + 2).min(width);
+    let rows = j.saturating_sub(1)..(j + 2).min(height);
+    let count = columns.len() * rows.len();
+    let sum: f64 = rows
+        .flat_map(|y| columns.clone().map(move |x| (x, y)))
+        .map(|(x, y)| pixels[(y * width + x) as usize].error())
+        .sum();
+    sum / count as f64
+}
+
+impl Default for Pixel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// 2^40 steps per unit
+const FIXED_ONE: f64 = (1u64 << 40) as f64;
+// Keeps a single runaway sample from overflowing the sums
+const MAX_SPLAT: f64 = 1e12;
+
+fn to_fixed(x: f64) -> i128 {
+    // NaN ends up as 0
+    (x.clamp(-MAX_SPLAT, MAX_SPLAT) * FIXED_ONE).round() as i128
+}
+
+// Filter-weighted sum of the samples that landed around a pixel
+// Kept in fixed point, so the splats of different tiles add up to the same
+// value in whatever order the tiles finish
+#[derive(Clone, Copy)]
+pub struct Splat {
+    color: [i128; 3],
+    weight: i128,
+}
+
+impl Splat {
+    pub const fn new() -> Self {
+        Self {
+            color: [0; 3],
+            weight: 0,
+        }
+    }
+
+    pub fn add(&mut self, color: Color, weight: f64) {
+        let weighted = weight * color;
+        self.color[0] += to_fixed(weighted.x);
+        self.color[1] += to_fixed(weighted.y);
+        self.color[2] += to_fixed(weighted.z);
+        self.weight += to_fixed(weight);
+    }
+
+    pub fn merge(&mut self, other: &Splat) {
+        for (sum, other) in self.color.iter_mut().zip(other.color) {
+            *sum += other;
+        }
+        self.weight += other.weight;
+    }
+
+    pub fn color_sum(&self) -> Color {
+        Color::new(
+            self.color[0] as f64,
+            self.color[1] as f64,
+            self.color[2] as f64,
+        ) / FIXED_ONE
+    }
+
+    pub fn weight_sum(&self) -> f64 {
+        self.weight as f64 / FIXED_ONE
+    }
+
+    // Black until some weight arrived, negative lobes can leave it at 0 or below
+    pub fn mean(&self) -> Color {
+        if self.weight <= 0 {
+            Color::ZERO
+        } else {
+            self.color_sum() / self.weight_sum()
+        }
+    }
+
+    // Raw fixed point values, for checkpoints
+    pub fn to_bits(&self) -> [i128; 4] {
+        [self.color[0], self.color[1], self.color[2], self.weight]
+    }
+
+    pub fn from_bits(bits: [i128; 4]) -> Self {
+        Self {
+            color: [bits[0], bits[1], bits[2]],
+            weight: bits[3],
+        }
+    }
+}
+
+impl Default for Splat {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Splats of one tile, covering the tile plus the reach of the filter
+#[derive(Clone)]
+pub struct SplatBuffer {
+    filter: Filter,
+    resolution: Resolution,
+    x0: u32,
+    y0: u32,
+    x1: u32,
+    y1: u32,
+    splats: Vec<Splat>,
+}
+
+impl SplatBuffer {
+    pub fn new(filter: Filter, resolution: Resolution, tile: &Tile) -> Self {
+        let margin = filter.radius().ceil() as u32;
+        let x0 = tile.x0.saturating_sub(margin);
+        let y0 = tile.y0.saturating_sub(margin);
+        let x1 = (tile.x1 + margin).min(resolution.width);
+        let y1 = (tile.y1 + margin).min(resolution.height);
+        Self {
+            filter,
+            resolution,
+            x0,
+            y0,
+            x1,
+            y1,
+            splats: vec![Splat::new(); ((x1 - x0) * (y1 - y0)) as usize],
+        }
+    }
+
+    fn get(&self, i: u32, j: u32) -> &Splat {
+        &self.splats[((j - self.y0) * (self.x1 - self.x0) + (i - self.x0)) as usize]
+    }
+
+    // Adds a sample at film position (x, y) to every pixel the filter reaches
+    // Pixel (i, j) covers [i, i + 1) x [j, j + 1)
+    pub fn add_sample(&mut self, x: f64, y: f64, color: Color) {
+        let radius = self.filter.radius();
+        let columns = pixel_span(x, radius, self.resolution.width);
+        let rows = pixel_span(y, radius, self.resolution.height);
+        for j in rows {
+            for i in columns.clone() {
+                let weight = self
+                    .filter
+                    .evaluate(x - (i as f64 + 0.5), y - (j as f64 + 0.5));
+                if weight != 0.0 {
+                    let index = ((j - self.y0) * (self.x1 - self.x0) + (i - self.x0)) as usize;
+                    self.splats[index].add(color, weight);
+                }
+            }
+        }
+    }
+}
+
+// Pixels whose centers are within radius of x
+fn pixel_span(x: f64, radius: f64, size: u32) -> std::ops::Range<u32> {
+    let first = (x - 0.5 - radius).ceil().max(0.0) as u32;
+    let last = ((x - 0.5 + radius).floor() + 1.0).clamp(0.0, size as f64) as u32;
+    first..last.max(first)
+}
+
+// Welford's online algorithm
+#[derive(Clone, Copy)]
+pub struct RunningStats {
+    pub n: u32,
+    pub mean: f64,
+    pub m2: f64,
+}
+
+impl RunningStats {
+    pub const fn new() -> Self {
+        Self {
+            n: 0,
+            mean: 0.0,
+            m2: 0.0,
+        }
+    }
+
+    pub fn add(&mut self, x: f64) {
+        self.n += 1;
+        let delta = x - self.mean;
+        self.mean += delta / self.n as f64;
+        let delta2 = x - self.mean;
+        self.m2 += delta * delta2
+    }
+
+    pub fn variance(&self) -> f64 {
+        if self.n > 1 {
+            self.m2 / (self.n as f64 - 1.0)
+        } else {
+            0.0
+        }
+    }
+}
+
+impl Default for RunningStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,102 @@
+use crate::prelude::*;
+
+/*
+    Pixel reconstruction filters
+
+    A sample at film position (x, y) adds its color to every pixel whose
+    center lies within the filter radius, weighted by the filter at the
+    offset between the two. A pixel is the weighted mean of what it got.
+
+    All filters are separable: weight(dx, dy) = f(dx) * f(dy).
+    Mitchell and Lanczos have negative lobes, so they sharpen and can ring.
+*/
+
+#[derive(Clone, Copy, Debug)]
+pub enum Filter {
+    // Every sample counts fully within the radius, 0.5 = plain pixel average
+    Box { radius: f64 },
+    // Linear falloff to 0 at the radius
+    Tent { radius: f64 },
+    // Shifted down so it reaches 0 at the radius
+    Gaussian { radius: f64, sigma: f64 },
+    // Mitchell-Netravali cubic, b = c = 1/3 is the recommended balance
+    Mitchell { radius: f64, b: f64, c: f64 },
+    // Windowed sinc with `radius` lobes
+    Lanczos { radius: f64 },
+}
+
+impl Filter {
+    pub const BOX: Filter = Filter::Box { radius: 0.5 };
+    pub const TENT: Filter = Filter::Tent { radius: 1.0 };
+    pub const GAUSSIAN: Filter = Filter::Gaussian {
+        radius: 1.5,
+        sigma: 0.5,
+    };
+    pub const MITCHELL: Filter = Filter::Mitchell {
+        radius: 2.0,
+        b: 1.0 / 3.0,
+        c: 1.0 / 3.0,
+    };
+    pub const LANCZOS: Filter = Filter::Lanczos { radius: 3.0 };
+
+    pub fn radius(&self) -> f64 {
+        match *self {
+            Filter::Box { radius }
+            | Filter::Tent { radius }
+            | Filter::Gaussian { radius, .. }
+            | Filter::Mitchell { radius, .. }
+            | Filter::Lanczos { radius } => radius,
+        }
+    }
+
+    // Weight of a sample at offset (dx, dy) from the pixel center
+    pub fn evaluate(&self, dx: f64, dy: f64) -> f64 {
+        self.evaluate_1d(dx) * self.evaluate_1d(dy)
+    }
+
+    fn evaluate_1d(&self, x: f64) -> f64 {
+        let x = x.abs();
+        if x > self.radius() {
+            return 0.0;
+        }
+        match *self {
+            Filter::Box { .. } => 1.0,
+            Filter::Tent { radius } => radius - x,
+            Filter::Gaussian { radius, sigma } => {
+                let gaussian = |x: f64| (-x * x / (2.0 * sigma * sigma)).exp();
+                (gaussian(x) - gaussian(radius)).max(0.0)
+            }
+            Filter::Mitchell { radius, b, c } => {
+                // the cubic is defined on [0, 2]
+                let x = 2.0 * x / radius;
+                if x < 1.0 {
+                    ((12.0 - 9.0 * b - 6.0 * c) * x * x * x
+                        + (-18.0 + 12.0 * b + 6.0 * c) * x * x
+                        + (6.0 - 2.0 * b))
+                        / 6.0
+                } else {
+                    ((-b - 6.0 * c) * x * x * x
+                        + (6.0 * b + 30.0 * c) * x * x
+                        + (-12.0 * b - 48.0 * c) * x
+                        + (8.0 * b + 24.0 * c))
+                        / 6.0
+                }
+            }
+            Filter::Lanczos { radius } => sinc(x) * sinc(x / radius),
+        }
+    }
+}
+
+impl Default for Filter {
+    fn default() -> Self {
+        Filter::BOX
+    }
+}
+
+fn sinc(x: f64) -> f64 {
+    if x < 1e-5 {
+        1.0
+    } else {
+        (PI * x).sin() / (PI * x)
+    }
+}
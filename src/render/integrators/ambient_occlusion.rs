@@ -0,0 +1,49 @@
+use crate::objects::Hit;
+use crate::prelude::*;
+use crate::render::World;
+use crate::render::integrators::Integrator;
+
+/*
+    Ambient occlusion
+
+    Fraction of the hemisphere above the first hit that is open within
+    `radius`, cosine weighted. Materials and lights are ignored, so it
+    shows the shape of the geometry.
+*/
+
+#[derive(Clone, Copy, Debug)]
+pub struct AmbientOcclusion {
+    // Rays per camera sample
+    pub samples: u32,
+    // Occluders further away than this do not count, in scene units
+    pub radius: f64,
+}
+
+impl Default for AmbientOcclusion {
+    fn default() -> Self {
+        Self {
+            samples: 16,
+            radius: 1.0,
+        }
+    }
+}
+
+impl Integrator for AmbientOcclusion {
+    fn trace(&self, ray: &Ray, world: &World, rng: &mut Rng) -> (Color, Option<Hit>) {
+        let Some(hit) = world.hit(ray, Interval::new(0.001, INFINITY), rng) else {
+            return (Color::ZERO, None);
+        };
+
+        let samples = self.samples.max(1);
+        let onb = Onb::from_w(hit.normal);
+        let mut open = 0;
+        for _ in 0..samples {
+            let direction = onb.to_world(sample_cosine_hemisphere(rng.random_2d()));
+            let probe = Ray::with_time(hit.point, direction, ray.time);
+            if !world.occluded(&probe, Interval::new(0.001, self.radius), rng) {
+                open += 1;
+            }
+        }
+        (Color::ONE * (open as f64 / samples as f64), Some(hit))
+    }
+}
@@ -0,0 +1,52 @@
+use crate::objects::{Hit, box_tests};
+use crate::prelude::*;
+use crate::render::World;
+use crate::render::aov::id_color;
+use crate::render::integrators::Integrator;
+
+/*
+    Debug views of the first hit, every value is mapped into [0, 1]
+*/
+
+#[derive(Clone, Copy, Debug, Default)]
+pub enum DebugView {
+    // Shading normal, [-1, 1] mapped to [0, 1]
+    #[default]
+    Normals,
+    // Distance along the ray, white up close fading to black at `far`
+    Depth {
+        far: f64,
+    },
+    // u in red, v in green
+    Uv,
+    // Bounding box tests for the camera ray, blue (none) to red (`max_tests` or more)
+    // Needs the "stats" feature, all blue without it
+    BvhCost {
+        max_tests: u32,
+    },
+    // A random color per material
+    MaterialId,
+}
+
+impl Integrator for DebugView {
+    fn trace(&self, ray: &Ray, world: &World, rng: &mut Rng) -> (Color, Option<Hit>) {
+        let tests_before = box_tests();
+        let hit = world.hit(ray, Interval::new(0.001, INFINITY), rng);
+        let tests = box_tests() - tests_before;
+
+        let color = match (*self, &hit) {
+            (DebugView::BvhCost { max_tests }, _) => {
+                get_heatmap_color(tests as f64 / max_tests.max(1) as f64)
+            }
+            (_, None) => Color::ZERO,
+            (DebugView::Normals, Some(hit)) => 0.5 * (hit.normal + Vec3::ONE),
+            (DebugView::Depth { far }, Some(hit)) => {
+                let distance = hit.t * ray.direction.length();
+                Color::ONE * (1.0 - Interval::UNIT.clamp(distance / far))
+            }
+            (DebugView::Uv, Some(hit)) => Color::new(hit.u, hit.v, 0.0),
+            (DebugView::MaterialId, Some(hit)) => id_color(hit.material_id),
+        };
+        (color, hit)
+    }
+}
@@ -0,0 +1,49 @@
+mod ambient_occlusion;
+mod debug;
+mod path_tracer;
+mod whitted;
+
+pub use ambient_occlusion::AmbientOcclusion;
+pub use debug::DebugView;
+pub use path_tracer::PathTracer;
+pub use whitted::Whitted;
+
+use crate::objects::{Hit, Light, LightSample};
+use crate::prelude::*;
+use crate::render::World;
+
+/*
+    An integrator decides the color seen along a camera ray, this is where
+    the light transport happens. The renderer takes care of everything
+    around it: pixels, samples, tiles and the film.
+*/
+
+// Debug tells checkpoints made with different integrators apart
+pub trait Integrator: Sync + std::fmt::Debug {
+    // Color seen along a camera ray, and the first thing it hit (used for the AOVs)
+    fn trace(&self, ray: &Ray, world: &World, rng: &mut Rng) -> (Color, Option<Hit>);
+}
+
+// Shadow ray from the hit towards a random point of the light
+// The sample and the BSDF towards it, None if it is blocked or the BSDF gives nothing
+fn sample_light(
+    ray: &Ray,
+    hit: &Hit,
+    world: &World,
+    light: &dyn Light,
+    rng: &mut Rng,
+) -> Option<(LightSample, Color)> {
+    let sample = light.sample(hit.point, rng)?;
+    let bsdf = hit.mat.eval(ray, hit, sample.direction);
+    if bsdf.near_zero() || sample.pdf <= 0.0 {
+        return None;
+    }
+
+    // stopping just short of the light
+    let shadow = Ray::with_time(hit.point, sample.direction, ray.time);
+    let unblocked = Interval::new(0.001, sample.distance * (1.0 - 1e-6) - 0.001);
+    if world.occluded(&shadow, unblocked, rng) {
+        return None;
+    }
+    Some((sample, bsdf))
+}
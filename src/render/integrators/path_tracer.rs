@@ -0,0 +1,113 @@
+use crate::objects::Hit;
+use crate::prelude::*;
+use crate::render::integrators::{Integrator, sample_light};
+use crate::render::{MisHeuristic, World};
+
+/*
+    Unidirectional path tracer
+
+    Every bounce samples one light directly and continues along a sampled
+    BSDF direction, the two are combined with multiple importance sampling.
+    Paths end when they leave the scene, get absorbed or lose the Russian
+    roulette.
+*/
+
+#[derive(Clone, Copy, Debug)]
+pub struct PathTracer {
+    // Safety cap on the bounces of a path, Russian roulette ends them long before
+    pub max_depth: u32,
+    // Bounces every path gets before Russian roulette may end it
+    pub min_depth: u32,
+    // How light sampling and BSDF sampling are combined
+    pub mis: MisHeuristic,
+}
+
+impl Default for PathTracer {
+    fn default() -> Self {
+        Self {
+            max_depth: 50,
+            min_depth: 3,
+            mis: MisHeuristic::Power,
+        }
+    }
+}
+
+// Where a ray left its last bounce, None for camera rays and delta lobes
+#[derive(Clone, Copy)]
+struct Bounce {
+    origin: Point3,
+    // of the BSDF sampling the ray's direction
+    pdf: f64,
+}
+
+impl Integrator for PathTracer {
+    fn trace(&self, ray: &Ray, world: &World, rng: &mut Rng) -> (Color, Option<Hit>) {
+        let mut color = Color::ZERO;
+        // what the light found further down the path is multiplied by
+        let mut throughput = Color::ONE;
+        let mut ray = Ray::with_time(ray.origin, ray.direction, ray.time);
+        let mut from: Option<Bounce> = None;
+        let mut first_hit = None;
+
+        for depth in 0..self.max_depth {
+            let Some(hit) = world.hit(&ray, Interval::new(0.001, INFINITY), rng) else {
+                color += throughput * world.backdrop;
+                break;
+            };
+
+            // light sampling at the last bounce could have found this emitter as well
+            let mut emission = hit.mat.emitted(hit.point);
+            if let Some(from) = from
+                && !emission.near_zero()
+            {
+                let light_pdf = world.light_pdf(hit.object_id, from.origin, ray.direction);
+                emission *= self.mis.weight(from.pdf, light_pdf);
+            }
+            color += throughput * emission;
+            color += throughput * self.sample_lights(&ray, &hit, world, rng);
+
+            let sample = hit.mat.sample(&ray, &hit, rng);
+            let origin = hit.point;
+            if depth == 0 {
+                first_hit = Some(hit);
+            }
+            let Some(sample) = sample else {
+                break;
+            };
+
+            throughput = throughput * sample.weight;
+            // Russian roulette, survivors make up for the paths that were ended
+            if depth + 1 >= self.min_depth {
+                let survival = throughput.max_component().min(1.0);
+                if rng.random_f64() >= survival {
+                    break;
+                }
+                throughput /= survival;
+            }
+
+            ray = Ray::with_time(origin, sample.direction, ray.time);
+            from = (!sample.delta).then_some(Bounce {
+                origin,
+                pdf: sample.pdf,
+            });
+        }
+        (color, first_hit)
+    }
+}
+
+impl PathTracer {
+    // Next-event estimation: light arriving at the hit straight from one random light
+    // Delta materials get nothing here, their eval is 0 everywhere
+    fn sample_lights(&self, ray: &Ray, hit: &Hit, world: &World, rng: &mut Rng) -> Color {
+        let Some((light, pick_odds)) = world.pick_light(rng) else {
+            return Color::ZERO;
+        };
+        let Some((sample, bsdf)) = sample_light(ray, hit, world, light, rng) else {
+            return Color::ZERO;
+        };
+
+        let light_pdf = sample.pdf * pick_odds;
+        let bsdf_pdf = hit.mat.pdf(ray, hit, sample.direction);
+        sample.emission * bsdf * self.mis.weight(light_pdf, bsdf_pdf) / light_pdf
+    }
+}
@@ -0,0 +1,76 @@
+use crate::objects::Hit;
+use crate::prelude::*;
+use crate::render::World;
+use crate::render::integrators::{Integrator, sample_light};
+
+/*
+    Whitted-style ray tracer, a fast preview
+
+    Mirrors and glass are followed, every other surface is shaded with one
+    shadow ray per light plus the backdrop as unshadowed ambient light.
+    There is no indirect light, so it is quick and noise-free but flat.
+*/
+
+#[derive(Clone, Copy, Debug)]
+pub struct Whitted {
+    // Mirror and glass bounces followed
+    pub max_depth: u32,
+    // How much of the backdrop lights every surface
+    pub ambient: f64,
+}
+
+impl Default for Whitted {
+    fn default() -> Self {
+        Self {
+            max_depth: 10,
+            ambient: 1.0,
+        }
+    }
+}
+
+impl Integrator for Whitted {
+    fn trace(&self, ray: &Ray, world: &World, rng: &mut Rng) -> (Color, Option<Hit>) {
+        let mut color = Color::ZERO;
+        let mut throughput = Color::ONE;
+        let mut ray = Ray::with_time(ray.origin, ray.direction, ray.time);
+        let mut first_hit = None;
+
+        for depth in 0..self.max_depth {
+            let Some(hit) = world.hit(&ray, Interval::new(0.001, INFINITY), rng) else {
+                color += throughput * world.backdrop;
+                break;
+            };
+            color += throughput * hit.mat.emitted(hit.point);
+
+            // only delta lobes are followed, the rest is shaded here
+            let sample = hit.mat.sample(&ray, &hit, rng).filter(|s| s.delta);
+            if sample.is_none() {
+                color += throughput * self.shade(&ray, &hit, world, rng);
+            }
+            let origin = hit.point;
+            if depth == 0 {
+                first_hit = Some(hit);
+            }
+            let Some(sample) = sample else {
+                break;
+            };
+
+            throughput = throughput * sample.weight;
+            ray = Ray::with_time(origin, sample.direction, ray.time);
+        }
+        (color, first_hit)
+    }
+}
+
+impl Whitted {
+    // Direct light from every light, and the ambient backdrop
+    fn shade(&self, ray: &Ray, hit: &Hit, world: &World, rng: &mut Rng) -> Color {
+        let mut color = self.ambient * world.backdrop * hit.mat.albedo(hit);
+        for light in world.lights() {
+            if let Some((sample, bsdf)) = sample_light(ray, hit, world, light.as_ref(), rng) {
+                color += sample.emission * bsdf / sample.pdf;
+            }
+        }
+        color
+    }
+}
@@ -0,0 +1,30 @@
+/*
+    Multiple importance sampling (Veach 1997)
+
+    Light reaching a non-delta bounce can be found two ways: by a shadow ray
+    towards a sampled light, or by the sampled BSDF direction happening to hit
+    an emitter. Each estimate is weighted by how likely its own strategy was to
+    pick that direction compared to the other one, so the weights sum to 1 and
+    each strategy covers the cases where it is good: light sampling for small
+    lights, BSDF sampling for glossy lobes facing large ones.
+*/
+
+#[derive(Clone, Copy, Debug, Default)]
+pub enum MisHeuristic {
+    // pdf / (pdf + other)
+    Balance,
+    // pdf^2 / (pdf^2 + other^2), sharper, usually less noisy
+    #[default]
+    Power,
+}
+
+impl MisHeuristic {
+    // Weight of a sample taken with density `pdf`, where the other strategy has `other_pdf`
+    pub fn weight(&self, pdf: f64, other_pdf: f64) -> f64 {
+        let (a, b) = match self {
+            MisHeuristic::Balance => (pdf, other_pdf),
+            MisHeuristic::Power => (pdf * pdf, other_pdf * other_pdf),
+        };
+        if a + b > 0.0 { a / (a + b) } else { 0.0 }
+    }
+}
@@ -1,9 +1,41 @@
+mod animation;
+mod aov;
 mod camera;
+mod checkpoint;
+mod crop;
+mod denoise;
+mod display;
+mod film;
+mod filter;
+mod integrators;
+mod mis;
+mod observer;
+mod progressive;
 mod renderer;
 mod samplers;
+mod stats;
+mod tiles;
 mod world;
+mod writers;
 
+pub use animation::Animation;
+pub use aov::{Aov, AovPreview, AovView, Surface, SurfaceStats};
 pub use camera::*;
+pub use checkpoint::Checkpoint;
+pub use crop::{Composited, CropOutput, CropWindow, Cropped};
+pub use denoise::Denoiser;
+pub use display::{DisplayTransform, Displayed, ToneMap};
+pub use film::{
+    Film, Image, ImageBuffer, Pixel, RunningStats, Splat, SplatBuffer, neighbourhood_error,
+};
+pub use filter::Filter;
+pub use integrators::{AmbientOcclusion, DebugView, Integrator, PathTracer, Whitted};
+pub use mis::MisHeuristic;
+pub use observer::*;
+pub use progressive::{Pass, Progressive, noise_estimate};
 pub use renderer::Renderer;
 pub use samplers::*;
+pub use stats::{Counters, RenderStats};
+pub use tiles::{Tile, TileOrder, tiles, tiles_in};
 pub use world::World;
+pub use writers::*;
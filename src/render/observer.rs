@@ -0,0 +1,112 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::prelude::*;
+use crate::render::{Film, Pass, RenderStats, Tile};
+
+/*
+    This file contains
+        1. RenderObserver (what a render reports while it runs)
+        2. CancelToken (stops a render from another thread)
+        3. ProgressBarObserver (terminal progress bar, "progress-bar" feature)
+*/
+
+// Hooks called by the renderer, all of them do nothing by default
+// Calls come from whichever thread finished the work, several may run at
+// once, so progress counts can arrive slightly out of order
+pub trait RenderObserver: Send + Sync {
+    // Pixels of the image finished so far, once at the start (resumed tiles)
+    // and after every tile
+    // With a sample_budget it starts over at 0 for the pixels of the
+    // second pass of adaptive sampling, whose tiles are reported too
+    fn progress(&self, _finished_pixels: u64, _total_pixels: u64) {}
+
+    // A tile was merged into the film, `colors` are its filtered colors there
+    // in Tile::pixels order, splats of the tiles around it included
+    fn tile_finished(&self, _tile: &Tile, _colors: &[Color]) {}
+
+    // A pass of a progressive render was merged into `film`
+    fn pass_finished(&self, _pass: &Pass, _film: &Film) {}
+
+    // The render is over, finished or cancelled
+    fn finished(&self, _stats: &RenderStats) {}
+}
+
+// Reports nothing
+impl RenderObserver for () {}
+
+// Shared flag the render loops check between samples
+// Clone it before rendering and call cancel from anywhere, e.g. a UI thread
+#[derive(Clone, Default, Debug)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    // Renders never clear it themselves, so a cancel before a render starts
+    // still stops it, call this before reusing the token for another render
+    pub fn reset(&self) {
+        self.0.store(false, Ordering::Relaxed);
+    }
+}
+
+#[cfg(feature = "progress-bar")]
+pub use progress_bar::ProgressBarObserver;
+
+#[cfg(feature = "progress-bar")]
+mod progress_bar {
+    use indicatif::{ProgressBar, ProgressStyle};
+
+    use crate::render::{RenderObserver, RenderStats};
+
+    // Terminal bar counting finished pixels
+    pub struct ProgressBarObserver {
+        bar: ProgressBar,
+    }
+
+    impl ProgressBarObserver {
+        pub fn new() -> Self {
+            Self::with_style(
+                ProgressStyle::with_template("[{elapsed_precise}] [{bar:40}] {percent:>3}%")
+                    .unwrap()
+                    .progress_chars("#>-"),
+            )
+        }
+
+        pub fn with_style(style: ProgressStyle) -> Self {
+            let bar = ProgressBar::new(0);
+            bar.set_style(style);
+            Self { bar }
+        }
+    }
+
+    impl Default for ProgressBarObserver {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    impl RenderObserver for ProgressBarObserver {
+        fn progress(&self, finished_pixels: u64, total_pixels: u64) {
+            // a smaller count of the same total is stale, unless the count starts over
+            let restart = self.bar.length() != Some(total_pixels) || finished_pixels == 0;
+            if restart || finished_pixels > self.bar.position() {
+                self.bar.set_length(total_pixels);
+                self.bar.set_position(finished_pixels);
+            }
+        }
+
+        fn finished(&self, _stats: &RenderStats) {
+            self.bar.finish();
+        }
+    }
+}
@@ -0,0 +1,92 @@
+use std::time::Duration;
+
+use crate::prelude::*;
+use crate::render::Film;
+
+/*
+    This file contains
+        1. Progressive (settings and stopping budget of a progressive render)
+        2. Pass (summary handed out after every pass)
+*/
+
+// The image is refined in passes over all pixels until one of the budgets is reached
+// Pixels that converged or reached `samples_range.1` of the renderer are left alone
+#[derive(Clone, Copy)]
+pub struct Progressive {
+    // Samples added to every unfinished pixel per pass
+    pub samples_per_pass: u32,
+    // Wall-clock budget, checked after every pass
+    pub time_budget: Option<Duration>,
+    // Samples per pixel to reach
+    pub target_samples: Option<u32>,
+    // Relative noise of the whole image to reach, see `noise_estimate`
+    pub target_noise: Option<f64>,
+}
+
+impl Default for Progressive {
+    fn default() -> Self {
+        Self {
+            samples_per_pass: 1,
+            time_budget: None,
+            target_samples: None,
+            target_noise: None,
+        }
+    }
+}
+
+impl Progressive {
+    pub fn with_time_budget(time_budget: Duration) -> Self {
+        Self {
+            time_budget: Some(time_budget),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_target_samples(target_samples: u32) -> Self {
+        Self {
+            target_samples: Some(target_samples),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_target_noise(target_noise: f64) -> Self {
+        Self {
+            target_noise: Some(target_noise),
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Pass {
+    // Starts at 0
+    pub index: u32,
+    // Time since the render started
+    pub elapsed: Duration,
+    pub min_samples: u32,
+    pub max_samples: u32,
+    pub noise: f64,
+}
+
+// RMS standard error of the pixel luminances relative to the mean image luminance
+// Only the pixels of the film's window count
+// Infinite until every pixel has at least two samples
+pub fn noise_estimate(film: &Film) -> f64 {
+    let pixels: Vec<_> = film.window_pixels().collect();
+    if pixels.is_empty() {
+        return 0.0;
+    }
+    if pixels.iter().any(|p| p.samples() < 2) {
+        return INFINITY;
+    }
+
+    let count = pixels.len() as f64;
+    let squared_error = pixels
+        .iter()
+        .map(|p| p.luminance.variance() / p.samples() as f64)
+        .sum::<f64>()
+        / count;
+    let brightness = pixels.iter().map(|p| p.luminance.mean).sum::<f64>() / count;
+
+    squared_error.sqrt() / brightness.max(0.001)
+}
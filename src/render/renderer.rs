@@ -1,231 +1,836 @@
-pub use std::fs::File;
-pub use std::io::{BufWriter, Write};
+use std::io::Write;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
 
-use indicatif::{ProgressBar, ProgressStyle};
 use rayon::prelude::*;
 
 use crate::prelude::*;
-use crate::render::{Camera, SampleFn, World};
+use crate::render::checkpoint::fingerprint;
+use crate::render::stats::count_camera_ray;
+use crate::render::{
+    Animation, Aov, Camera, CancelToken, Checkpoint, Counters, CropOutput, CropWindow, Denoiser,
+    DisplayTransform, Film, Filter, Image, ImageBuffer, ImageFormat, IndependentSampler,
+    Integrator, Pass, PathTracer, Pixel, Progressive, RenderObserver, RenderStats, SampleFn,
+    Shutter, SplatBuffer, Surface, Tile, TileOrder, World, neighbourhood_error, noise_estimate,
+    tiles, tiles_in,
+};
 
-pub struct Renderer {
-    pub max_depth: u32,
+pub struct Renderer<I = PathTracer> {
+    // What the color seen along each camera ray is
+    pub integrator: I,
     pub time_sampler: Option<SampleFn>,
+    // Values of every random decision of a sample, time too unless time_sampler is set
+    pub sampler: Arc<dyn Sampler>,
     pub samples_range: (u32, u32),
-    pub tolerable_cv: f64,
+    // A pixel stops once the error around it (Film::error) is below this
+    pub tolerable_error: f64,
+    // Samples taken by every pixel of a tile between two error checks
+    pub samples_per_check: u32,
+    // Average samples per pixel of the whole image, what adaptive sampling
+    // leaves unused goes to the pixels with the highest error
+    pub sample_budget: Option<u32>,
+    // Same seed, same image, whichever render path is used
+    pub seed: u64,
+    // How samples are weighted into the pixels around them
+    pub filter: Filter,
+    pub tile_size: u32,
+    pub tile_order: TileOrder,
+    // Only these pixels are rendered, the film still has the camera's resolution
+    pub crop: Option<CropWindow>,
+    // Whether written images cover the crop window or the whole frame
+    pub crop_output: CropOutput,
+    // Where to periodically save finished tiles, and resume from
+    pub checkpoint: Option<Checkpoint>,
+    pub format: ImageFormat,
+    pub display: DisplayTransform,
+    // Used by denoise, the film itself always keeps the noisy image
+    pub denoiser: Denoiser,
+    // Auxiliary buffers written by write_aovs
+    pub aovs: Vec<Aov>,
+    // Told about progress, finished tiles and passes
+    // A terminal progress bar by default, with the "progress-bar" feature
+    pub observer: Arc<dyn RenderObserver>,
+    // Renders stop early once it is cancelled, see RenderStats::cancelled
+    // It stays cancelled, reset it before the next render
+    pub cancel: CancelToken,
 }
 
-impl Renderer {
+impl<I: Integrator + Default> Default for Renderer<I> {
+    fn default() -> Self {
+        Self {
+            integrator: I::default(),
+            time_sampler: None,
+            sampler: Arc::new(IndependentSampler),
+            samples_range: (8, 1000),
+            tolerable_error: 0.01,
+            samples_per_check: 8,
+            sample_budget: None,
+            seed: 0,
+            filter: Filter::BOX,
+            tile_size: 32,
+            tile_order: TileOrder::Scanline,
+            crop: None,
+            crop_output: CropOutput::Cropped,
+            checkpoint: None,
+            format: ImageFormat::Png,
+            display: DisplayTransform::default(),
+            denoiser: Denoiser::Off,
+            aovs: Vec::new(),
+            observer: default_observer(),
+            cancel: CancelToken::new(),
+        }
+    }
+}
+
+// The path tracer with every other setting at its default
+// Use it instead of Default::default() in struct literals that leave the
+// integrator out, the integrator type cannot be inferred there
+impl Renderer<PathTracer> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<I: Integrator> Renderer<I> {
     pub fn multi_threaded_render(
         &self,
         camera: &Camera,
         world: &World,
-        file: File,
-        heatmap_file: Option<File>,
-        style: Option<ProgressStyle>,
-    ) -> std::io::Result<()> {
-        // --- PROGRESS BAR ---
-        let style = style.unwrap_or(
-            ProgressStyle::with_template("[{elapsed_precise}] [{bar:40}] {percent:>3}%")
-                .unwrap()
-                .progress_chars("#>-"),
-        );
-
-        let total_pixels = camera.resolution.width * camera.resolution.height;
-        let pb = Arc::new(ProgressBar::new(total_pixels as u64));
-        pb.set_style(style);
+    ) -> std::io::Result<(Film, RenderStats)> {
+        let start = Instant::now();
+        let (progress, pending) = self.start_tiles(camera)?;
 
         // --- MAIN LOOP ---
-        let pixel_colors: Vec<(Color, u32)> = (0..camera.resolution.height)
+        // workers pull tiles from a shared counter so the tile order is respected
+        let saved = Mutex::new(progress.saved_copy());
+        let progress = Mutex::new(progress);
+        let next_tile = AtomicUsize::new(0);
+        (0..rayon::current_num_threads())
             .into_par_iter()
-            .flat_map(|j| {
-                let pb = pb.clone();
-                (0..camera.resolution.width).into_par_iter().map(move |i| {
-                    let (pixel_color, samples) = self.pixel_color(camera, world, i, j);
-                    pb.inc(1);
-                    (pixel_color, samples)
-                })
-            })
-            .collect();
+            .try_for_each(|_| {
+                while !self.cancel.is_cancelled()
+                    && let Some(tile) = pending.get(next_tile.fetch_add(1, Ordering::Relaxed))
+                {
+                    let before = Counters::now();
+                    let (pixels, splats) = self.render_tile(camera, world, tile);
+                    let counters = Counters::now() - before;
+                    let merged = progress
+                        .lock()
+                        .unwrap()
+                        .finish_tile(tile, pixels, &splats, counters);
+                    // reported after letting go of the progress, so the other workers keep going
+                    if let Some(merged) = merged {
+                        self.report_tile(tile, merged, camera.shutter(), &saved)?;
+                    }
+                }
+                Ok::<(), std::io::Error>(())
+            })?;
+
+        let progress = progress.into_inner().unwrap();
+        self.finish_tiles(camera, world, progress, start, true)
+    }
+
+    pub fn single_threaded_render(
+        &self,
+        camera: &Camera,
+        world: &World,
+    ) -> std::io::Result<(Film, RenderStats)> {
+        let start = Instant::now();
+        let (mut progress, pending) = self.start_tiles(camera)?;
+        let saved = Mutex::new(progress.saved_copy());
 
-        // --- WRITE TO MAIN FILE ---
-        let mut writer = BufWriter::new(file);
-        writeln!(writer, "P3")?;
-        writeln!(
-            writer,
-            "{} {}",
-            camera.resolution.width, camera.resolution.height
-        )?;
-        writeln!(writer, "255")?;
-
-        let mut max_samples_used = 0;
-        for &(color, samples) in &pixel_colors {
-            write_color(&mut writer, color)?;
-            max_samples_used = max_samples_used.max(samples);
-        }
-
-        // --- WRITE TO HEATMAP FILE ---
-        if let Some(heatmap_file) = heatmap_file {
-            let mut heatmap_writer = BufWriter::new(heatmap_file);
-            writeln!(heatmap_writer, "P3")?;
-            writeln!(
-                heatmap_writer,
-                "{} {}",
-                camera.resolution.width, camera.resolution.height
-            )?;
-            writeln!(heatmap_writer, "255")?;
-
-            for &(_, samples) in &pixel_colors {
-                let intensity = (samples - self.samples_range.0) as f64
-                    / (max_samples_used - self.samples_range.0) as f64;
-                write_color(&mut heatmap_writer, get_heatmap_color(intensity))?;
+        // --- MAIN LOOP ---
+        for tile in &pending {
+            if self.cancel.is_cancelled() {
+                break;
+            }
+            let before = Counters::now();
+            let (pixels, splats) = self.render_tile(camera, world, tile);
+            let counters = Counters::now() - before;
+            if let Some(merged) = progress.finish_tile(tile, pixels, &splats, counters) {
+                self.report_tile(tile, merged, camera.shutter(), &saved)?;
             }
         }
 
-        pb.finish();
-        Ok(())
+        self.finish_tiles(camera, world, progress, start, false)
     }
 
-    pub fn single_threaded_render(
+    // Second pass of adaptive sampling and statistics, once the tiles are done or cancelled
+    fn finish_tiles(
         &self,
         camera: &Camera,
         world: &World,
-        file: File,
-        heatmap_file: Option<File>,
-        style: Option<ProgressStyle>,
-    ) -> std::io::Result<()> {
-        // --- PROGRESS BAR ---
-        let style = style.unwrap_or(
-            ProgressStyle::with_template("[{elapsed_precise}] [{bar:40}] {percent:>3}%")
-                .unwrap()
-                .progress_chars("#>-"),
+        mut progress: TileProgress<'_, I>,
+        start: Instant,
+        parallel: bool,
+    ) -> std::io::Result<(Film, RenderStats)> {
+        // before finish, which keeps the checkpoint only if this was cancelled too
+        progress.counters += self.redistribute(camera, world, &mut progress.film, parallel, true);
+        let (film, counters) = progress.finish()?;
+        let stats = self.stats(counters, &film, start);
+        self.observer.finished(&stats);
+        Ok((film, stats))
+    }
+
+    fn stats(&self, counters: Counters, film: &Film, start: Instant) -> RenderStats {
+        RenderStats {
+            cancelled: self.cancel.is_cancelled(),
+            ..RenderStats::new(counters, film, start.elapsed())
+        }
+    }
+
+    // Renders the whole image in passes and calls `on_pass` with the film after each one,
+    // until a budget of `progressive` is reached or every pixel is finished
+    // Once every pixel is finished, a last pass spends what is left of `sample_budget`
+    // The checkpoint is not used, write the film from `on_pass` instead
+    pub fn progressive_render(
+        &self,
+        camera: &Camera,
+        world: &World,
+        progressive: Progressive,
+        mut on_pass: impl FnMut(&Film, &Pass) -> std::io::Result<()>,
+    ) -> std::io::Result<Film> {
+        let start = Instant::now();
+        let window = self.window(camera);
+        let mut film = Film::with_window(camera.resolution, window);
+        let tiles = tiles_in(
+            camera.resolution,
+            window,
+            self.tile_size,
+            TileOrder::Scanline,
         );
+        let target = progressive.target_samples.unwrap_or(self.samples_range.1);
+        let per_pass = progressive.samples_per_pass.max(1);
+        let mut counters = Counters::default();
 
-        let total_pixels = camera.resolution.width * camera.resolution.height;
-        let pb = ProgressBar::new(total_pixels as u64);
-        pb.set_style(style);
+        for index in 0.. {
+            let unfinished = film
+                .window_pixels()
+                .any(|p| p.samples() < target.min(self.samples_range.1) && !p.converged);
+            if !unfinished {
+                let extra = if target >= self.samples_range.1 {
+                    self.redistribute(camera, world, &mut film, true, false)
+                } else {
+                    Counters::default()
+                };
+                if extra.camera_rays > 0 {
+                    counters += extra;
+                    let pass = self.pass(index, start, &film);
+                    on_pass(&film, &pass)?;
+                    self.observer.pass_finished(&pass, &film);
+                }
+                break;
+            }
 
-        // --- SETUP WRITER FOR MAIN FILE ---
-        let mut writer = BufWriter::new(file);
-        writeln!(writer, "P3")?;
-        writeln!(
-            writer,
-            "{} {}",
-            camera.resolution.width, camera.resolution.height
-        )?;
-        writeln!(writer, "255")?;
+            // --- MAIN LOOP ---
+            let rendered: Vec<_> = tiles
+                .par_iter()
+                .map(|tile| {
+                    let before = Counters::now();
+                    let mut pixels = film.tile_pixels(tile);
+                    let splats = self.sample_tile(camera, world, tile, &mut pixels, |samples| {
+                        (samples + per_pass).min(target)
+                    });
+                    (pixels, splats, Counters::now() - before)
+                })
+                .collect();
+            // a cancelled pass is kept, its pixels just have fewer samples
+            for (tile, (pixels, splats, tile_counters)) in tiles.iter().zip(rendered) {
+                film.merge_tile(tile, pixels, &splats);
+                counters += tile_counters;
+            }
 
-        // for writing to heatmap
-        let mut samples_used: Vec<u32> = Vec::new();
-        let mut max_samples_used = 0;
+            let pass = self.pass(index, start, &film);
+            on_pass(&film, &pass)?;
+            self.observer.pass_finished(&pass, &film);
 
-        // --- MAIN LOOP ---
-        for j in 0..camera.resolution.height {
-            for i in 0..camera.resolution.width {
-                let (pixel_color, samples) = self.pixel_color(camera, world, i, j);
-                write_color(&mut writer, pixel_color)?;
-                max_samples_used = max_samples_used.max(samples);
-                samples_used.push(samples);
-                pb.inc(1);
+            let out_of_time = progressive
+                .time_budget
+                .is_some_and(|budget| pass.elapsed >= budget);
+            let quiet_enough = progressive
+                .target_noise
+                .is_some_and(|noise| pass.noise <= noise);
+            if out_of_time || quiet_enough || self.cancel.is_cancelled() {
+                break;
             }
         }
 
-        // --- WRITE TO HEATMAP FILE ---
-        if let Some(heatmap_file) = heatmap_file {
-            let mut heatmap_writer = BufWriter::new(heatmap_file);
-            writeln!(heatmap_writer, "P3")?;
-            writeln!(
-                heatmap_writer,
-                "{} {}",
-                camera.resolution.width, camera.resolution.height
-            )?;
-            writeln!(heatmap_writer, "255")?;
-
-            for &samples in &samples_used {
-                let intensity = (samples - self.samples_range.0) as f64
-                    / (max_samples_used - self.samples_range.0) as f64;
-                write_color(&mut heatmap_writer, get_heatmap_color(intensity))?;
+        self.observer.finished(&self.stats(counters, &film, start));
+        Ok(film)
+    }
+
+    // Renders the frames of `animation` one after the other, each through the
+    // camera with that frame's shutter, and hands them to `on_frame`
+    // Stops after a cancelled frame, its checkpoint only resumes with
+    // first_frame set to that frame
+    pub fn render_animation(
+        &self,
+        camera: &Camera,
+        world: &World,
+        animation: Animation,
+        mut on_frame: impl FnMut(u32, &Film, &RenderStats) -> std::io::Result<()>,
+    ) -> std::io::Result<()> {
+        for frame in animation.frames() {
+            let camera = camera.clone().with_shutter(animation.shutter(frame));
+            let (film, stats) = self.multi_threaded_render(&camera, world)?;
+            on_frame(frame, &film, &stats)?;
+            if stats.cancelled {
+                break;
             }
         }
-
-        pb.finish();
         Ok(())
     }
 
-    // Returns the pixel color and number of samples
-    pub fn pixel_color(&self, camera: &Camera, world: &World, i: u32, j: u32) -> (Color, u32) {
-        let mut stats = RunningStats::new();
-        let mut pixel_color = Color::new(0.0, 0.0, 0.0);
-        for s in 0..self.samples_range.1 {
-            let time = match &self.time_sampler {
-                None => random_unit_f64(),
-                Some(sampler) => sampler(s + i + j * camera.resolution.width),
+    fn pass(&self, index: u32, start: Instant, film: &Film) -> Pass {
+        Pass {
+            index,
+            elapsed: start.elapsed(),
+            min_samples: film.min_samples(),
+            max_samples: film.max_samples(),
+            noise: noise_estimate(film),
+        }
+    }
+
+    /*
+        Second pass of adaptive sampling
+
+        Gives the samples of `sample_budget` the film does not have yet to its
+        pixels in proportion to their error, without going over the maximum.
+        With `report`, the tiles it samples go to the observer like the first
+        pass's do. Returns the work it did.
+    */
+    fn redistribute(
+        &self,
+        camera: &Camera,
+        world: &World,
+        film: &mut Film,
+        parallel: bool,
+        report: bool,
+    ) -> Counters {
+        let Some(budget) = self.sample_budget else {
+            return Counters::default();
+        };
+        if self.cancel.is_cancelled() {
+            return Counters::default();
+        }
+        let (width, height) = (film.resolution.width, film.resolution.height);
+        let window = film.window();
+        let total = budget as u64 * window.area() as u64;
+        let taken: u64 = film.pixels().iter().map(|p| p.samples() as u64).sum();
+        let errors: Vec<f64> = (0..height)
+            .flat_map(|j| (0..width).map(move |i| (i, j)))
+            .map(|(i, j)| film.error(i, j))
+            .collect();
+        let error_sum: f64 = errors.iter().sum();
+        if taken >= total || error_sum <= 0.0 {
+            return Counters::default();
+        }
+        let remaining = (total - taken) as f64;
+        let targets: Vec<u32> = film
+            .pixels()
+            .iter()
+            .zip(&errors)
+            .map(|(pixel, error)| {
+                let extra = (remaining * error / error_sum) as u32;
+                pixel
+                    .samples()
+                    .saturating_add(extra)
+                    .min(self.samples_range.1)
+            })
+            .collect();
+        if targets
+            .iter()
+            .zip(film.pixels())
+            .all(|(&t, p)| t <= p.samples())
+        {
+            return Counters::default();
+        }
+
+        // only tiles with a pixel to sample, they are merged and reported as they finish
+        let pending: Vec<(Tile, Vec<Pixel>)> = tiles_in(
+            camera.resolution,
+            window,
+            self.tile_size,
+            TileOrder::Scanline,
+        )
+        .into_iter()
+        .filter(|tile| {
+            tile.pixels()
+                .any(|(i, j)| targets[(j * width + i) as usize] > film.samples(i, j))
+        })
+        .map(|tile| {
+            let pixels = film.tile_pixels(&tile);
+            (tile, pixels)
+        })
+        .collect();
+        let total_pixels: u64 = pending.iter().map(|(tile, _)| tile.area() as u64).sum();
+        let merged = Mutex::new((film, 0, Counters::default()));
+        let render = |(tile, mut pixels): (Tile, Vec<Pixel>)| {
+            let before = Counters::now();
+            let mut splats = SplatBuffer::new(self.filter, camera.resolution, &tile);
+            for ((i, j), pixel) in tile.pixels().zip(&mut pixels) {
+                let target = targets[(j * width + i) as usize];
+                self.take_samples(camera, world, (i, j), pixel, &mut splats, target);
+            }
+            let counters = Counters::now() - before;
+
+            let (colors, finished_pixels) = {
+                let mut merged = merged.lock().unwrap();
+                let (film, finished_pixels, total) = &mut *merged;
+                film.merge_tile(&tile, pixels, &splats);
+                *finished_pixels += tile.area() as u64;
+                *total += counters;
+                (report.then(|| film.tile_colors(&tile)), *finished_pixels)
             };
-            let ray = camera.sample_ray(i, j, time);
-            let ray_color = ray_color(&ray, self.max_depth, world);
-            pixel_color += ray_color;
-            // Update stats
-            stats.add(luminance(ray_color));
-
-            if stats.n >= self.samples_range.0 {
-                // standard error
-                if stats.variance().sqrt() < self.tolerable_cv * stats.mean.max(0.001) {
-                    break;
-                }
+            if let Some(colors) = colors {
+                self.observer.tile_finished(&tile, &colors);
+                self.observer.progress(finished_pixels, total_pixels);
             }
+        };
+        if report {
+            self.observer.progress(0, total_pixels);
         }
-        (pixel_color / stats.n as f64, stats.n)
+        if parallel {
+            pending.into_par_iter().for_each(render);
+        } else {
+            pending.into_iter().for_each(render);
+        }
+        merged.into_inner().unwrap().2
     }
-}
 
-fn ray_color(ray: &Ray, depth: u32, world: &World) -> Color {
-    if depth == 0 {
-        return Color::new(0.0, 0.0, 0.0);
+    // Mean colors of the film cleaned up by the renderer's denoiser
+    pub fn denoise(&self, film: &Film) -> ImageBuffer {
+        self.denoiser.apply(film)
+    }
+
+    // Writes an auxiliary buffer in the renderer's output format, returns how long it took
+    // HDR formats get the raw values, LDR formats a preview
+    pub fn write_aov(
+        &self,
+        film: &Film,
+        aov: Aov,
+        writer: impl Write,
+    ) -> std::io::Result<Duration> {
+        let start = Instant::now();
+        if self.format.is_hdr() {
+            self.write_cropped(&film.aov(aov), writer)?;
+        } else {
+            self.write_cropped(&film.aov_preview(aov), writer)?;
+        }
+        Ok(start.elapsed())
+    }
+
+    // Writes every buffer in `aovs`, `open` gives the destination of each
+    // Returns how long they took, opening them included
+    pub fn write_aovs<W: Write>(
+        &self,
+        film: &Film,
+        mut open: impl FnMut(Aov) -> std::io::Result<W>,
+    ) -> std::io::Result<Duration> {
+        let start = Instant::now();
+        for &aov in &self.aovs {
+            self.write_aov(film, aov, open(aov)?)?;
+        }
+        Ok(start.elapsed())
     }
 
-    //TODO: the return logic is very suspicious and I dont know how to fix it yet. I will come back later
-    if let Some(hit) = world.geometry.hit(ray, Interval::new(0.001, INFINITY)) {
-        // hit something
-        let emission = hit.mat.emitted(hit.point);
-        if let Some(scatter) = hit.mat.scatter(ray, &hit) {
-            emission + scatter.attenuation * ray_color(&scatter.ray_out, depth - 1, world)
+    // Writes a film (or a view of it) in the renderer's output format, returns how long it took
+    // The display transform only applies to LDR formats
+    pub fn write_image(&self, image: &impl Image, writer: impl Write) -> std::io::Result<Duration> {
+        let start = Instant::now();
+        if self.format.is_hdr() {
+            self.write_cropped(image, writer)?;
         } else {
-            emission
+            self.write_cropped(&self.display.view(image), writer)?;
         }
-    } else {
-        world.backdrop
+        Ok(start.elapsed())
+    }
+
+    // Writes only the crop window of a full-size image if the output is cropped
+    fn write_cropped(&self, image: &impl Image, writer: impl Write) -> std::io::Result<()> {
+        match (self.crop, self.crop_output) {
+            (Some(crop), CropOutput::Cropped) => {
+                let window = crop.clamp(image.resolution());
+                self.format.write_encoded(&window.crop(image), writer)
+            }
+            _ => self.format.write_encoded(image, writer),
+        }
+    }
+
+    // Part of the camera's image that is rendered
+    fn window(&self, camera: &Camera) -> CropWindow {
+        let full = CropWindow::full(camera.resolution);
+        self.crop.map_or(full, |crop| crop.clamp(camera.resolution))
+    }
+
+    // Pixels of the tile in scanline order, and the colors their samples splat into the film
+    pub fn render_tile(
+        &self,
+        camera: &Camera,
+        world: &World,
+        tile: &Tile,
+    ) -> (Vec<Pixel>, SplatBuffer) {
+        let mut pixels = vec![Pixel::new(); tile.area() as usize];
+        let splats = self.sample_tile(camera, world, tile, &mut pixels, |_| self.samples_range.1);
+        (pixels, splats)
+    }
+
+    /*
+        Samples every pixel of the tile up to `limit(samples it had before)`
+
+        Pixels move together, `samples_per_check` samples at a time. After
+        each step, the ones whose error and neighbours' errors are low enough
+        (neighbourhood_error over the tile) stop for good. Checks only happen
+        at multiples of `samples_per_check`, so the pixels stop at the same
+        count however the samples are split into calls.
+    */
+    fn sample_tile(
+        &self,
+        camera: &Camera,
+        world: &World,
+        tile: &Tile,
+        pixels: &mut [Pixel],
+        limit: impl Fn(u32) -> u32,
+    ) -> SplatBuffer {
+        let mut splats = SplatBuffer::new(self.filter, camera.resolution, tile);
+        let limits: Vec<u32> = pixels
+            .iter()
+            .map(|p| limit(p.samples()).min(self.samples_range.1))
+            .collect();
+        loop {
+            let mut sampled = false;
+            for (((i, j), pixel), &limit) in tile.pixels().zip(pixels.iter_mut()).zip(&limits) {
+                let target = self.next_check(pixel.samples()).min(limit);
+                if !pixel.converged && pixel.samples() < target {
+                    self.take_samples(camera, world, (i, j), pixel, &mut splats, target);
+                    sampled = true;
+                }
+            }
+            if !sampled || self.cancel.is_cancelled() {
+                break;
+            }
+
+            let (width, height) = (tile.x1 - tile.x0, tile.y1 - tile.y0);
+            let stopping: Vec<bool> = (0..height)
+                .flat_map(|y| (0..width).map(move |x| (x, y)))
+                .map(|(x, y)| {
+                    let pixel = &pixels[(y * width + x) as usize];
+                    !pixel.converged
+                        && pixel
+                            .samples()
+                            .is_multiple_of(self.samples_per_check.max(1))
+                        && self.may_stop(pixel)
+                        && neighbourhood_error(pixels, width, height, x, y) < self.tolerable_error
+                })
+                .collect();
+            for (pixel, stopping) in pixels.iter_mut().zip(stopping) {
+                pixel.converged |= stopping;
+            }
+        }
+        splats
+    }
+
+    // Sample count of the next error check after `samples`
+    fn next_check(&self, samples: u32) -> u32 {
+        let step = self.samples_per_check.max(1);
+        (samples / step + 1) * step
+    }
+
+    fn may_stop(&self, pixel: &Pixel) -> bool {
+        pixel.samples() >= self.samples_range.0.max(2)
+    }
+
+    // Adds samples to the pixel until it has `target`
+    // Sample s of a pixel is the same whether it is taken in one go or over several calls
+    fn take_samples(
+        &self,
+        camera: &Camera,
+        world: &World,
+        (i, j): (u32, u32),
+        pixel: &mut Pixel,
+        splats: &mut SplatBuffer,
+        target: u32,
+    ) {
+        let pixel_index = (j * camera.resolution.width + i) as u64;
+        while pixel.samples() < target && !self.cancel.is_cancelled() {
+            count_camera_ray();
+            let s = pixel.samples();
+            let mut rng = Rng::for_sampler(self.seed, pixel_index, s, self.sampler.as_ref());
+            let u = match &self.time_sampler {
+                None => rng.random_f64(),
+                Some(sampler) => sampler(mix64(pixel_index ^ mix64(self.seed)), s, &mut rng),
+            };
+            let time = camera.shutter().time_at(u);
+            // uniform inside the pixel, the filter decides how much each sample counts
+            let [dx, dy] = rng.random_2d();
+            let x = i as f64 + dx;
+            let y = j as f64 + dy;
+            let ray = camera.ray_at(x, y, time, &mut rng);
+            let (color, hit) = self.integrator.trace(&ray, world, &mut rng);
+            let surface = hit.map(|hit| Surface {
+                albedo: hit.mat.albedo(&hit),
+                normal: hit.normal,
+                position: hit.point,
+                depth: camera.depth(hit.point),
+                object_id: hit.object_id,
+                material_id: hit.material_id,
+            });
+            pixel.add_sample(color, surface.as_ref());
+            splats.add_sample(x, y, color);
+        }
+    }
+
+    // Tells the observer about a merged tile and saves the checkpoint if it is due
+    fn report_tile(
+        &self,
+        tile: &Tile,
+        merged: MergedTile,
+        shutter: Shutter,
+        saved: &Mutex<Option<SavedProgress>>,
+    ) -> std::io::Result<()> {
+        self.observer.tile_finished(tile, &merged.colors);
+        self.observer
+            .progress(merged.finished_pixels, merged.total_pixels);
+        match merged.snapshot {
+            Some(snapshot) => self.save_snapshot(snapshot, shutter, saved),
+            None => Ok(()),
+        }
+    }
+
+    // Brings the checkpoint's copy of the progress up to date with a snapshot and saves it
+    // Snapshots may arrive out of order, merging tiles does not depend on it
+    fn save_snapshot(
+        &self,
+        snapshot: Snapshot,
+        shutter: Shutter,
+        saved: &Mutex<Option<SavedProgress>>,
+    ) -> std::io::Result<()> {
+        let Some(checkpoint) = &self.checkpoint else {
+            return Ok(());
+        };
+        let mut saved = saved.lock().unwrap();
+        let Some(saved) = saved.as_mut() else {
+            return Ok(());
+        };
+        for update in snapshot.tiles {
+            saved
+                .film
+                .merge_tile(&update.tile, update.pixels, &update.splats);
+            saved.done[update.tile.index] = true;
+        }
+        checkpoint.save(
+            &saved.film,
+            &saved.done,
+            shutter,
+            self.tile_size,
+            self.seed,
+            self.settings_fingerprint(),
+        )
+    }
+
+    // Settings that change what a saved pixel means, a checkpoint only resumes with the same ones
+    // The time sampler is a closure, only whether there is one can be told apart
+    fn settings_fingerprint(&self) -> u64 {
+        fingerprint(&format!(
+            "{:?} {:?} {:?} {:?} {:?} {:?} {}",
+            self.integrator,
+            self.sampler,
+            self.filter,
+            self.samples_range,
+            self.tolerable_error,
+            self.samples_per_check,
+            self.time_sampler.is_some(),
+        ))
+    }
+
+    // Resumes from the checkpoint if there is one, returns the tiles left to render
+    fn start_tiles(&self, camera: &Camera) -> std::io::Result<(TileProgress<'_, I>, Vec<Tile>)> {
+        // tiles outside the window are never done
+        let window = self.window(camera);
+        let tile_count = tiles(camera.resolution, self.tile_size, self.tile_order).len();
+        let tiles = tiles_in(camera.resolution, window, self.tile_size, self.tile_order);
+
+        let resumed = match &self.checkpoint {
+            Some(checkpoint) => {
+                let shutter = camera.shutter();
+                checkpoint.load(
+                    camera.resolution,
+                    window,
+                    shutter,
+                    self.tile_size,
+                    self.seed,
+                    self.settings_fingerprint(),
+                )?
+            }
+            None => None,
+        };
+        let (film, done) = match resumed {
+            Some((film, done)) if done.len() == tile_count => (film, done),
+            Some(_) => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    "checkpoint tile count does not match",
+                ));
+            }
+            None => (
+                Film::with_window(camera.resolution, window),
+                vec![false; tile_count],
+            ),
+        };
+
+        let pending = tiles.into_iter().filter(|tile| !done[tile.index]).collect();
+        let mut progress = TileProgress {
+            renderer: self,
+            shutter: camera.shutter(),
+            settings: self.settings_fingerprint(),
+            film,
+            done,
+            finished_pixels: 0,
+            counters: Counters::default(),
+            last_save: Instant::now(),
+            unsaved: Vec::new(),
+        };
+        progress.finished_pixels = progress
+            .film
+            .window_pixels()
+            .filter(|p| p.samples() > 0)
+            .count() as u64;
+        self.observer
+            .progress(progress.finished_pixels, progress.total_pixels());
+        Ok((progress, pending))
     }
 }
 
-// Helper struct for adaptive sampling
-struct RunningStats {
-    n: u32,
-    mean: f64,
-    m2: f64,
+// Bookkeeping of finished tiles, shared by the render workers
+struct TileProgress<'a, I> {
+    renderer: &'a Renderer<I>,
+    shutter: Shutter,
+    // see Renderer::settings_fingerprint
+    settings: u64,
+    film: Film,
+    done: Vec<bool>,
+    finished_pixels: u64,
+    // work of the tiles finished in this run
+    counters: Counters,
+    last_save: Instant,
+    // tiles finished since the last snapshot, kept only with a checkpoint
+    unsaved: Vec<TileUpdate>,
 }
 
-impl RunningStats {
-    fn new() -> Self {
-        Self {
-            n: 0,
-            mean: 0.0,
-            m2: 0.0,
+// What finish_tile hands out, used once the progress is let go
+struct MergedTile {
+    // the tile's filtered colors in the film, in Tile::pixels order
+    colors: Vec<Color>,
+    finished_pixels: u64,
+    total_pixels: u64,
+    snapshot: Option<Snapshot>,
+}
+
+// Tiles finished since the previous snapshot, saved without holding the progress
+struct Snapshot {
+    tiles: Vec<TileUpdate>,
+}
+
+// What a finished tile merged into the film
+struct TileUpdate {
+    tile: Tile,
+    pixels: Vec<Pixel>,
+    splats: SplatBuffer,
+}
+
+// The checkpoint's own film and finished tiles, the snapshots are merged into it
+struct SavedProgress {
+    film: Film,
+    done: Vec<bool>,
+}
+
+impl<I> TileProgress<'_, I> {
+    fn total_pixels(&self) -> u64 {
+        self.film.window().area() as u64
+    }
+
+    // Merges a finished tile, with a snapshot when the checkpoint is due for a save
+    // None when the render was cancelled, the tile is then dropped
+    fn finish_tile(
+        &mut self,
+        tile: &Tile,
+        pixels: Vec<Pixel>,
+        splats: &SplatBuffer,
+        counters: Counters,
+    ) -> Option<MergedTile> {
+        self.counters += counters;
+        // the tile may have been cut short
+        if self.renderer.cancel.is_cancelled() {
+            return None;
         }
+        if self.renderer.checkpoint.is_some() {
+            self.unsaved.push(TileUpdate {
+                tile: *tile,
+                pixels: pixels.clone(),
+                splats: splats.clone(),
+            });
+        }
+        self.film.merge_tile(tile, pixels, splats);
+        self.done[tile.index] = true;
+        self.finished_pixels += tile.area() as u64;
+
+        Some(MergedTile {
+            colors: self.film.tile_colors(tile),
+            finished_pixels: self.finished_pixels,
+            total_pixels: self.total_pixels(),
+            snapshot: self.snapshot(),
+        })
     }
 
-    fn add(&mut self, x: f64) {
-        self.n += 1;
-        let delta = x - self.mean;
-        self.mean += delta / self.n as f64;
-        let delta2 = x - self.mean;
-        self.m2 += delta * delta2
+    fn snapshot(&mut self) -> Option<Snapshot> {
+        let checkpoint = self.renderer.checkpoint.as_ref()?;
+        if self.last_save.elapsed() < checkpoint.interval {
+            return None;
+        }
+        self.last_save = Instant::now();
+        Some(Snapshot {
+            tiles: std::mem::take(&mut self.unsaved),
+        })
     }
 
-    // Welford's online algorithm
-    fn variance(&self) -> f64 {
-        if self.n > 1 {
-            self.m2 / (self.n as f64 - 1.0)
-        } else {
-            0.0
+    // Starting point of the checkpoint's copy, None without a checkpoint
+    // Taken before the workers start, the snapshots only carry what changed since
+    fn saved_copy(&self) -> Option<SavedProgress> {
+        self.renderer.checkpoint.as_ref()?;
+        Some(SavedProgress {
+            film: self.film.clone(),
+            done: self.done.clone(),
+        })
+    }
+
+    // A cancelled render keeps its checkpoint up to date, so it can be resumed
+    fn finish(self) -> std::io::Result<(Film, Counters)> {
+        if let Some(checkpoint) = &self.renderer.checkpoint {
+            if self.renderer.cancel.is_cancelled() {
+                let renderer = self.renderer;
+                checkpoint.save(
+                    &self.film,
+                    &self.done,
+                    self.shutter,
+                    renderer.tile_size,
+                    renderer.seed,
+                    self.settings,
+                )?;
+            } else {
+                checkpoint.remove()?;
+            }
         }
+        Ok((self.film, self.counters))
     }
 }
+
+#[cfg(feature = "progress-bar")]
+fn default_observer() -> Arc<dyn RenderObserver> {
+    Arc::new(crate::render::ProgressBarObserver::new())
+}
+
+#[cfg(not(feature = "progress-bar"))]
+fn default_observer() -> Arc<dyn RenderObserver> {
+    Arc::new(())
+}
@@ -1,10 +1,12 @@
 use crate::prelude::*;
 
 // --- LOW DISCREPENCY SEQUENCES ---
-pub type SampleFn = Arc<dyn Fn(u32) -> f64 + Send + Sync>;
+// Maps (pixel, sample index) to a value in [0, 1), drawing from the sample's rng if it needs to
+// `pixel` identifies the pixel and the render seed, each pixel gets its own scrambled sequence
+pub type SampleFn = Arc<dyn Fn(u64, u32, &mut Rng) -> f64 + Send + Sync>;
 
 pub fn uniform_sampler() -> SampleFn {
-    Arc::new(move |_| random_unit_f64())
+    Arc::new(move |_, _, rng| rng.random_f64())
 }
 
 pub fn halton(mut index: u32, base: u32) -> f64 {
@@ -21,41 +23,356 @@ pub fn halton(mut index: u32, base: u32) -> f64 {
 }
 
 pub fn halton_sampler(base: u32) -> SampleFn {
-    Arc::new(move |idx| halton(idx, base))
+    Arc::new(move |pixel, index, _| scrambled_radical_inverse(index, base, hash(pixel, 0, base)))
 }
 
-pub fn sobol(n: u32) -> f64 {
-    let mut result = 0.0;
-    let mut f = 0.5;
-    let mut i = n;
+// Dimension `dimension` of the Sobol sequence, dimension 0 is the van der Corput sequence
+// Only the first SOBOL_DIMENSIONS exist, others panic
+pub fn sobol(index: u32, dimension: usize) -> f64 {
+    assert!(
+        dimension < SOBOL_DIMENSIONS,
+        "Sobol dimension {dimension} out of range, only {SOBOL_DIMENSIONS} are tabulated"
+    );
+    fixed_to_unit(sobol_bits(index, dimension))
+}
 
-    while i != 0 {
-        if i & 1 != 0 {
-            result += f;
+pub fn sobol_sampler() -> SampleFn {
+    Arc::new(|pixel, index, _| {
+        fixed_to_unit(nested_uniform_scramble(
+            sobol_bits(index, 0),
+            hash(pixel, 4, 0) as u32,
+        ))
+    })
+}
+
+/*
+    Sobol direction numbers of Joe and Kuo (new-joe-kuo-6.21201)
+    Dimension d + 1 has a primitive polynomial of degree s with inner
+    coefficients a, and starts from the odd numbers m
+*/
+pub const SOBOL_DIMENSIONS: usize = 21;
+
+const JOE_KUO: [(usize, u32, [u32; 7]); SOBOL_DIMENSIONS - 1] = [
+    (1, 0, [1, 0, 0, 0, 0, 0, 0]),
+    (2, 1, [1, 3, 0, 0, 0, 0, 0]),
+    (3, 1, [1, 3, 1, 0, 0, 0, 0]),
+    (3, 2, [1, 1, 1, 0, 0, 0, 0]),
+    (4, 1, [1, 1, 3, 3, 0, 0, 0]),
+    (4, 4, [1, 3, 5, 13, 0, 0, 0]),
+    (5, 2, [1, 1, 5, 5, 17, 0, 0]),
+    (5, 4, [1, 1, 5, 5, 5, 0, 0]),
+    (5, 7, [1, 1, 7, 11, 19, 0, 0]),
+    (5, 11, [1, 1, 5, 1, 1, 0, 0]),
+    (5, 13, [1, 1, 1, 3, 11, 0, 0]),
+    (5, 14, [1, 3, 5, 5, 31, 0, 0]),
+    (6, 1, [1, 3, 3, 9, 7, 49, 0]),
+    (6, 13, [1, 1, 1, 15, 21, 21, 0]),
+    (6, 16, [1, 3, 1, 13, 27, 49, 0]),
+    (6, 19, [1, 1, 1, 15, 7, 5, 0]),
+    (6, 22, [1, 3, 1, 15, 13, 25, 0]),
+    (6, 25, [1, 1, 5, 5, 19, 61, 0]),
+    (7, 1, [1, 3, 7, 11, 23, 15, 103]),
+    (7, 4, [1, 3, 7, 13, 13, 15, 69]),
+];
+
+// Generator matrix columns of every dimension, as 0.32 fixed point
+static SOBOL_MATRICES: [[u32; 32]; SOBOL_DIMENSIONS] = sobol_matrices();
+
+const fn sobol_matrices() -> [[u32; 32]; SOBOL_DIMENSIONS] {
+    let mut matrices = [[0; 32]; SOBOL_DIMENSIONS];
+    let mut k = 0;
+    while k < 32 {
+        matrices[0][k] = 1 << (31 - k);
+        k += 1;
+    }
+    let mut d = 1;
+    while d < SOBOL_DIMENSIONS {
+        let (s, a, m) = JOE_KUO[d - 1];
+        let v = &mut matrices[d];
+        let mut k = 0;
+        while k < 32 {
+            if k < s {
+                v[k] = m[k] << (31 - k);
+            } else {
+                // recurrence of the polynomial
+                let mut x = v[k - s] ^ (v[k - s] >> s);
+                let mut j = 1;
+                while j < s {
+                    if (a >> (s - 1 - j)) & 1 == 1 {
+                        x ^= v[k - j];
+                    }
+                    j += 1;
+                }
+                v[k] = x;
+            }
+            k += 1;
         }
-        i >>= 1;
-        f *= 0.5;
+        d += 1;
     }
+    matrices
+}
 
+fn sobol_bits(mut index: u32, dimension: usize) -> u32 {
+    let matrix = &SOBOL_MATRICES[dimension];
+    let mut result = 0;
+    let mut k = 0;
+    while index != 0 {
+        if index & 1 != 0 {
+            result ^= matrix[k];
+        }
+        index >>= 1;
+        k += 1;
+    }
     result
 }
 
-pub fn sobol_sampler() -> SampleFn {
-    Arc::new(move |idx| sobol(idx))
+// --- SAMPLERS ---
+// Values for every dimension of a pixel's samples, see base::Sampler
+
+// Plain random numbers, every dimension is left to the sample's rng
+#[derive(Clone, Copy, Debug, Default)]
+pub struct IndependentSampler;
+
+impl Sampler for IndependentSampler {
+    fn dimensions(&self) -> u32 {
+        0
+    }
+
+    fn sample_1d(&self, pixel: u64, index: u32, dimension: u32) -> f64 {
+        hash_to_unit(hash(pixel, index, dimension))
+    }
+}
+
+/*
+    Jittered strata: every x_strata * y_strata samples of a pixel fall one
+    into each stratum, in an order shuffled per pixel and dimension.
+    1D dimensions use x_strata * y_strata strata, 2D ones an x by y grid.
+*/
+#[derive(Clone, Copy, Debug)]
+pub struct StratifiedSampler {
+    pub x_strata: u32,
+    pub y_strata: u32,
+}
+
+impl Default for StratifiedSampler {
+    fn default() -> Self {
+        Self {
+            x_strata: 4,
+            y_strata: 4,
+        }
+    }
+}
+
+impl StratifiedSampler {
+    fn count(&self) -> u32 {
+        self.x_strata.max(1) * self.y_strata.max(1)
+    }
+
+    // Stratum of the sample, a fresh shuffle for every `count` samples
+    fn stratum(&self, pixel: u64, index: u32, dimension: u32) -> u32 {
+        let count = self.count();
+        let round = hash(pixel, index / count, dimension);
+        permutation_element(index % count, count, round as u32)
+    }
+}
+
+impl Sampler for StratifiedSampler {
+    fn sample_1d(&self, pixel: u64, index: u32, dimension: u32) -> f64 {
+        let stratum = self.stratum(pixel, index, dimension);
+        let jitter = hash_to_unit(hash(pixel ^ JITTER, index, dimension));
+        (stratum as f64 + jitter) / self.count() as f64
+    }
+
+    fn sample_2d(&self, pixel: u64, index: u32, dimension: u32) -> [f64; 2] {
+        let (x_strata, y_strata) = (self.x_strata.max(1), self.y_strata.max(1));
+        let stratum = self.stratum(pixel, index, dimension);
+        let jitter = hash(pixel ^ JITTER, index, dimension);
+        [
+            ((stratum % x_strata) as f64 + hash_to_unit(jitter)) / x_strata as f64,
+            ((stratum / x_strata) as f64 + hash_to_unit(mix64(jitter))) / y_strata as f64,
+        ]
+    }
+}
+
+/*
+    Halton sequence, dimension d uses the radical inverse in the d-th prime
+    The digits are shuffled by random permutations, one per digit position,
+    pixel and dimension. Unshuffled, large bases would keep the first few
+    samples of a pixel close to 0, and neighbours would repeat one pattern.
+*/
+#[derive(Clone, Copy, Debug, Default)]
+pub struct HaltonSampler;
+
+const PRIMES: [u32; 32] = [
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97,
+    101, 103, 107, 109, 113, 127, 131,
+];
+
+impl Sampler for HaltonSampler {
+    // higher primes need many samples before they fill the square evenly
+    fn dimensions(&self) -> u32 {
+        PRIMES.len() as u32
+    }
+
+    fn sample_1d(&self, pixel: u64, index: u32, dimension: u32) -> f64 {
+        let seed = hash(pixel, 0, dimension);
+        scrambled_radical_inverse(index, PRIMES[dimension as usize], seed)
+    }
+}
+
+// Radical inverse with each digit position mapped through its own permutation
+// The zero digits past the last one are permuted as well, up to double precision
+fn scrambled_radical_inverse(mut index: u32, base: u32, seed: u64) -> f64 {
+    let inverse_base = 1.0 / base as f64;
+    let mut f = inverse_base;
+    let mut result = 0.0;
+    let mut position = 0;
+    while index > 0 || f > 1e-16 {
+        let digit = permutation_element(index % base, base, hash(seed, position, 0) as u32);
+        result += digit as f64 * f;
+        index /= base;
+        f *= inverse_base;
+        position += 1;
+    }
+    result.min(1.0 - f64::EPSILON / 2.0)
+}
+
+/*
+    Owen-scrambled Sobol (0, 2)-sequence, padded to any number of dimensions
+
+    Every pair of dimensions gets the first two Sobol dimensions, which are
+    stratified over every power-of-two square of the pair. The index is
+    shuffled and the digits randomly scrambled per pixel and dimension
+    (Burley 2020), so pairs are independent of each other and pixels of
+    their neighbours, while each pair keeps its stratification.
+*/
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PaddedSobolSampler;
+
+impl Sampler for PaddedSobolSampler {
+    fn sample_1d(&self, pixel: u64, index: u32, dimension: u32) -> f64 {
+        let seed = hash(pixel, 1, dimension);
+        let index = nested_uniform_scramble(index, seed as u32);
+        let x = nested_uniform_scramble(index.reverse_bits(), (seed >> 32) as u32);
+        fixed_to_unit(x)
+    }
+
+    fn sample_2d(&self, pixel: u64, index: u32, dimension: u32) -> [f64; 2] {
+        let seed = hash(pixel, 2, dimension);
+        let index = nested_uniform_scramble(index, seed as u32);
+        let x = nested_uniform_scramble(index.reverse_bits(), (seed >> 32) as u32);
+        let y = nested_uniform_scramble(sobol_bits(index, 1), mix64(seed) as u32);
+        [fixed_to_unit(x), fixed_to_unit(y)]
+    }
+}
+
+/*
+    Owen-scrambled Sobol sequence over its first SOBOL_DIMENSIONS dimensions
+
+    All dimensions come from one multi-dimensional sequence, so any subset
+    of them is well spread, unlike the padded pairs. Every pixel shuffles
+    the index and scrambles each dimension with its own seeds (Burley 2020).
+*/
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SobolSampler;
+
+impl Sampler for SobolSampler {
+    fn dimensions(&self) -> u32 {
+        SOBOL_DIMENSIONS as u32
+    }
+
+    fn sample_1d(&self, pixel: u64, index: u32, dimension: u32) -> f64 {
+        // one shuffle for all dimensions keeps them a single sequence
+        let index = nested_uniform_scramble(index, hash(pixel, 3, u32::MAX) as u32);
+        let seed = hash(pixel, 3, dimension) as u32;
+        fixed_to_unit(nested_uniform_scramble(
+            sobol_bits(index, dimension as usize),
+            seed,
+        ))
+    }
+}
+
+// --- SCRAMBLING ---
+
+// Salt so the jitter is unrelated to the stratum shuffle
+const JITTER: u64 = 0x5851_F42D_4C95_7F2D;
+
+fn hash(pixel: u64, index: u32, dimension: u32) -> u64 {
+    mix64(pixel ^ mix64(((dimension as u64) << 32) | index as u64))
+}
+
+// Uniform in [0, 1) from the top 53 bits
+fn hash_to_unit(bits: u64) -> f64 {
+    (bits >> 11) as f64 / (1u64 << 53) as f64
+}
+
+// 0.32 fixed point to [0, 1)
+fn fixed_to_unit(bits: u32) -> f64 {
+    bits as f64 / (1u64 << 32) as f64
+}
+
+// Hash that only lets lower bits affect higher ones (Laine and Karras 2011),
+// so on bit-reversed values it is a nested uniform (Owen) scramble
+fn laine_karras_permutation(mut x: u32, seed: u32) -> u32 {
+    x = x.wrapping_add(seed);
+    x ^= x.wrapping_mul(0x6c50_b47c);
+    x ^= x.wrapping_mul(0xb82f_1e52);
+    x ^= x.wrapping_mul(0xc7af_e638);
+    x ^= x.wrapping_mul(0x8d22_f6e6);
+    x
+}
+
+// Owen scramble of a 0.32 fixed point value
+fn nested_uniform_scramble(x: u32, seed: u32) -> u32 {
+    laine_karras_permutation(x.reverse_bits(), seed).reverse_bits()
+}
+
+// Element i of a random permutation of 0..n chosen by `seed` (Kensler 2013)
+fn permutation_element(mut i: u32, n: u32, seed: u32) -> u32 {
+    let mut w = n - 1;
+    w |= w >> 1;
+    w |= w >> 2;
+    w |= w >> 4;
+    w |= w >> 8;
+    w |= w >> 16;
+    loop {
+        i ^= seed;
+        i = i.wrapping_mul(0xe170_893d);
+        i ^= seed >> 16;
+        i ^= (i & w) >> 4;
+        i ^= seed >> 8;
+        i = i.wrapping_mul(0x0929_eb3f);
+        i ^= seed >> 23;
+        i ^= (i & w) >> 1;
+        i = i.wrapping_mul(1 | seed >> 27);
+        i = i.wrapping_mul(0x6935_fa69);
+        i ^= (i & w) >> 11;
+        i = i.wrapping_mul(0x74dc_b303);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0x9e50_1cc3);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0xc860_a3df);
+        i &= w;
+        i ^= i >> 5;
+        if i < n {
+            break;
+        }
+    }
+    i.wrapping_add(seed) % n
 }
 
 /// Returns a sampler that draws samples according to the given PDF in [0, max)
 pub fn sampler_from_randomable(rv: impl Randomable + 'static) -> SampleFn {
-    Arc::new(move |_| rv.sample())
+    Arc::new(move |_, _, rng| rv.sample(rng))
 }
 
 pub trait Randomable: Send + Sync {
     const SUPREMUM: f64;
     fn eval(&self, _x: f64) -> f64;
-    fn sample(&self) -> f64 {
+    fn sample(&self, rng: &mut Rng) -> f64 {
         loop {
-            let x: f64 = random_unit_f64();
-            let choice: f64 = rand::random_range(0.0..=Self::SUPREMUM);
+            let x: f64 = rng.random_f64();
+            let choice: f64 = rng.random_range(0.0..Self::SUPREMUM);
             if choice <= self.eval(x) {
                 return x;
             }
@@ -99,3 +416,51 @@ impl Randomizer {
     pub const CUSTOM: Custom = Custom;
     pub const LOGISTIC: Logistic = Logistic;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // First points of dimensions 1 to 3 printed by Joe and Kuo's sobol.cc,
+    // which walks the indices in Gray code order
+    #[test]
+    fn sobol_matches_joe_kuo() {
+        let reference = [
+            [0.0, 0.5, 0.75, 0.25, 0.375, 0.875, 0.625, 0.125],
+            [0.0, 0.5, 0.25, 0.75, 0.375, 0.875, 0.125, 0.625],
+            [0.0, 0.5, 0.25, 0.75, 0.625, 0.125, 0.875, 0.375],
+        ];
+        for (dimension, points) in reference.iter().enumerate() {
+            for (i, &expected) in points.iter().enumerate() {
+                let gray = (i ^ (i >> 1)) as u32;
+                assert_eq!(
+                    sobol(gray, dimension),
+                    expected,
+                    "dimension {dimension}, point {i}"
+                );
+            }
+        }
+    }
+
+    // The first 2^m points of every dimension fall one in each interval of width 2^-m
+    #[test]
+    fn sobol_dimensions_are_stratified() {
+        for dimension in 0..SOBOL_DIMENSIONS {
+            for m in 0..10 {
+                let count = 1u32 << m;
+                let mut seen = vec![false; count as usize];
+                for index in 0..count {
+                    let cell = (sobol(index, dimension) * count as f64) as usize;
+                    assert!(!seen[cell], "dimension {dimension}, {count} points");
+                    seen[cell] = true;
+                }
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "out of range")]
+    fn sobol_rejects_missing_dimensions() {
+        sobol(0, SOBOL_DIMENSIONS);
+    }
+}
@@ -0,0 +1,225 @@
+use std::cell::Cell;
+use std::fmt;
+use std::io::Write;
+use std::ops::{AddAssign, Sub};
+use std::time::Duration;
+
+use crate::objects::{box_tests, intersection_tests};
+use crate::render::Film;
+
+/*
+    This file contains
+        1. Counters (work done on one thread, counted as it happens)
+        2. RenderStats (report of a whole render)
+*/
+
+thread_local! {
+    static CAMERA_RAYS: Cell<u64> = const { Cell::new(0) };
+    static PATH_RAYS: Cell<u64> = const { Cell::new(0) };
+    static SHADOW_RAYS: Cell<u64> = const { Cell::new(0) };
+}
+
+fn increment(counter: &'static std::thread::LocalKey<Cell<u64>>) {
+    counter.with(|count| count.set(count.get() + 1));
+}
+
+// Once per sample, always counted
+pub(crate) fn count_camera_ray() {
+    increment(&CAMERA_RAYS);
+}
+
+// Once per ray, compiled out without the "stats" feature
+pub(crate) fn count_path_ray() {
+    if cfg!(feature = "stats") {
+        increment(&PATH_RAYS);
+    }
+}
+
+pub(crate) fn count_shadow_ray() {
+    if cfg!(feature = "stats") {
+        increment(&SHADOW_RAYS);
+    }
+}
+
+// Work done on the current thread so far
+// Only camera rays are counted without the "stats" feature
+// Take the difference of two snapshots to get the work in between
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub struct Counters {
+    pub camera_rays: u64,
+    // rays looking for the closest hit: camera rays and bounces
+    pub path_rays: u64,
+    // rays only checking whether something is in the way
+    pub shadow_rays: u64,
+    pub intersection_tests: u64,
+    pub box_tests: u64,
+}
+
+impl Counters {
+    pub fn now() -> Self {
+        Self {
+            camera_rays: CAMERA_RAYS.with(Cell::get),
+            path_rays: PATH_RAYS.with(Cell::get),
+            shadow_rays: SHADOW_RAYS.with(Cell::get),
+            intersection_tests: intersection_tests(),
+            box_tests: box_tests(),
+        }
+    }
+}
+
+impl Sub for Counters {
+    type Output = Counters;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self {
+            camera_rays: self.camera_rays - rhs.camera_rays,
+            path_rays: self.path_rays - rhs.path_rays,
+            shadow_rays: self.shadow_rays - rhs.shadow_rays,
+            intersection_tests: self.intersection_tests - rhs.intersection_tests,
+            box_tests: self.box_tests - rhs.box_tests,
+        }
+    }
+}
+
+impl AddAssign for Counters {
+    fn add_assign(&mut self, rhs: Self) {
+        self.camera_rays += rhs.camera_rays;
+        self.path_rays += rhs.path_rays;
+        self.shadow_rays += rhs.shadow_rays;
+        self.intersection_tests += rhs.intersection_tests;
+        self.box_tests += rhs.box_tests;
+    }
+}
+
+// What a render did and how long it took
+// Counters only cover this run, not tiles resumed from a checkpoint
+// The renderer fills in render_time, and write_image and write_aov return the
+// time to add to write_time, build_time is up to the caller
+#[derive(Clone, Debug, Default)]
+pub struct RenderStats {
+    pub counters: Counters,
+    // the render was stopped through its CancelToken, the film is incomplete
+    pub cancelled: bool,
+    // over the pixels of the window, entry 0 counts the ones without samples,
+    // entry k the ones with 2^(k-1) to 2^k - 1 samples
+    pub samples_histogram: Vec<u64>,
+    pub build_time: Duration,
+    pub render_time: Duration,
+    pub write_time: Duration,
+}
+
+impl RenderStats {
+    pub fn new(counters: Counters, film: &Film, render_time: Duration) -> Self {
+        let mut samples_histogram = Vec::new();
+        for pixel in film.window_pixels() {
+            let bucket = (u32::BITS - pixel.samples().leading_zeros()) as usize;
+            if samples_histogram.len() <= bucket {
+                samples_histogram.resize(bucket + 1, 0);
+            }
+            samples_histogram[bucket] += 1;
+        }
+        Self {
+            counters,
+            samples_histogram,
+            render_time,
+            ..Default::default()
+        }
+    }
+
+    pub fn rays(&self) -> u64 {
+        self.counters.path_rays + self.counters.shadow_rays
+    }
+
+    // Segments per camera path, the camera ray included
+    pub fn average_path_length(&self) -> f64 {
+        if self.counters.camera_rays == 0 {
+            0.0
+        } else {
+            self.counters.path_rays as f64 / self.counters.camera_rays as f64
+        }
+    }
+
+    pub fn rays_per_second(&self) -> f64 {
+        let seconds = self.render_time.as_secs_f64();
+        if seconds > 0.0 {
+            self.rays() as f64 / seconds
+        } else {
+            0.0
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        let c = &self.counters;
+        let histogram: Vec<String> = self.samples_histogram.iter().map(u64::to_string).collect();
+        format!(
+            concat!(
+                "{{\n",
+                "  \"cancelled\": {},\n",
+                "  \"camera_rays\": {},\n",
+                "  \"path_rays\": {},\n",
+                "  \"shadow_rays\": {},\n",
+                "  \"rays\": {},\n",
+                "  \"intersection_tests\": {},\n",
+                "  \"box_tests\": {},\n",
+                "  \"average_path_length\": {},\n",
+                "  \"rays_per_second\": {},\n",
+                "  \"samples_histogram\": [{}],\n",
+                "  \"build_seconds\": {},\n",
+                "  \"render_seconds\": {},\n",
+                "  \"write_seconds\": {}\n",
+                "}}\n"
+            ),
+            self.cancelled,
+            c.camera_rays,
+            c.path_rays,
+            c.shadow_rays,
+            self.rays(),
+            c.intersection_tests,
+            c.box_tests,
+            self.average_path_length(),
+            self.rays_per_second(),
+            histogram.join(", "),
+            self.build_time.as_secs_f64(),
+            self.render_time.as_secs_f64(),
+            self.write_time.as_secs_f64(),
+        )
+    }
+
+    pub fn write_json(&self, mut writer: impl Write) -> std::io::Result<()> {
+        writer.write_all(self.to_json().as_bytes())
+    }
+}
+
+impl fmt::Display for RenderStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let c = &self.counters;
+        if self.cancelled {
+            writeln!(f, "cancelled")?;
+        }
+        writeln!(f, "camera rays         {}", c.camera_rays)?;
+        writeln!(
+            f,
+            "rays                {} ({} shadow)",
+            self.rays(),
+            c.shadow_rays
+        )?;
+        writeln!(f, "intersection tests  {}", c.intersection_tests)?;
+        writeln!(f, "bounding box tests  {}", c.box_tests)?;
+        writeln!(f, "average path length {:.2}", self.average_path_length())?;
+        writeln!(f, "rays per second     {:.0}", self.rays_per_second())?;
+        writeln!(f, "samples per pixel")?;
+        for (bucket, &count) in self.samples_histogram.iter().enumerate() {
+            if count == 0 {
+                continue;
+            }
+            let (low, high) = match bucket {
+                0 => (0, 0),
+                k => (1u64 << (k - 1), (1u64 << k) - 1),
+            };
+            writeln!(f, "  {low:>6} - {high:<6} {count}")?;
+        }
+        writeln!(f, "build time          {:.2?}", self.build_time)?;
+        writeln!(f, "render time         {:.2?}", self.render_time)?;
+        write!(f, "write time          {:.2?}", self.write_time)
+    }
+}
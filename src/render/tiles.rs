@@ -0,0 +1,160 @@
+use crate::render::{CropWindow, Resolution};
+
+/*
+    This file contains
+        1. Tile (rectangle of pixels rendered as one unit of work)
+        2. TileOrder (order in which tiles are handed out)
+*/
+
+#[derive(Clone, Copy)]
+pub struct Tile {
+    // index in the scanline grid of tiles, independent of the order
+    pub index: usize,
+    pub x0: u32,
+    pub y0: u32,
+    pub x1: u32,
+    pub y1: u32,
+}
+
+impl Tile {
+    pub fn width(&self) -> u32 {
+        self.x1 - self.x0
+    }
+
+    pub fn height(&self) -> u32 {
+        self.y1 - self.y0
+    }
+
+    pub fn area(&self) -> u32 {
+        self.width() * self.height()
+    }
+
+    // The part of the tile inside the window, None if they do not overlap
+    pub fn clip(&self, window: CropWindow) -> Option<Tile> {
+        let tile = Tile {
+            index: self.index,
+            x0: self.x0.max(window.x0),
+            y0: self.y0.max(window.y0),
+            x1: self.x1.min(window.x1),
+            y1: self.y1.min(window.y1),
+        };
+        (tile.x0 < tile.x1 && tile.y0 < tile.y1).then_some(tile)
+    }
+
+    // Pixels of the tile in scanline order
+    pub fn pixels(&self) -> impl Iterator<Item = (u32, u32)> + '_ {
+        (self.y0..self.y1).flat_map(move |j| (self.x0..self.x1).map(move |i| (i, j)))
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum TileOrder {
+    // Left to right, top to bottom
+    Scanline,
+    // Along a Hilbert curve, keeps consecutive tiles close together
+    Hilbert,
+    // Outwards from the center of the image, so the subject shows up first
+    Spiral,
+}
+
+// Splits the image into tiles of at most `tile_size` pixels a side
+pub fn tiles(resolution: Resolution, tile_size: u32, order: TileOrder) -> Vec<Tile> {
+    let tile_size = tile_size.max(1);
+    let nx = resolution.width.div_ceil(tile_size);
+    let ny = resolution.height.div_ceil(tile_size);
+
+    let tile_at = |tx: u32, ty: u32| Tile {
+        index: (ty * nx + tx) as usize,
+        x0: tx * tile_size,
+        y0: ty * tile_size,
+        x1: ((tx + 1) * tile_size).min(resolution.width),
+        y1: ((ty + 1) * tile_size).min(resolution.height),
+    };
+
+    let coords: Vec<(u32, u32)> = match order {
+        TileOrder::Scanline => (0..ny)
+            .flat_map(|ty| (0..nx).map(move |tx| (tx, ty)))
+            .collect(),
+        TileOrder::Hilbert => {
+            let side = nx.max(ny).next_power_of_two();
+            let mut coords: Vec<(u32, u32)> = (0..ny)
+                .flat_map(|ty| (0..nx).map(move |tx| (tx, ty)))
+                .collect();
+            coords.sort_by_key(|&(tx, ty)| hilbert_index(side, tx, ty));
+            coords
+        }
+        TileOrder::Spiral => spiral(nx, ny),
+    };
+
+    coords.into_iter().map(|(tx, ty)| tile_at(tx, ty)).collect()
+}
+
+// Tiles of the full image that overlap the window, clipped to it
+// They keep their index and order in the full grid
+pub fn tiles_in(
+    resolution: Resolution,
+    window: CropWindow,
+    tile_size: u32,
+    order: TileOrder,
+) -> Vec<Tile> {
+    tiles(resolution, tile_size, order)
+        .into_iter()
+        .filter_map(|tile| tile.clip(window))
+        .collect()
+}
+
+// Distance of (x, y) along the Hilbert curve filling a side x side square
+fn hilbert_index(side: u32, mut x: u32, mut y: u32) -> u64 {
+    let mut d = 0u64;
+    let mut s = side / 2;
+    while s > 0 {
+        let rx = (x & s > 0) as u32;
+        let ry = (y & s > 0) as u32;
+        d += (s as u64) * (s as u64) * ((3 * rx) ^ ry) as u64;
+        // rotate the quadrant so the curve stays continuous
+        if ry == 0 {
+            if rx == 1 {
+                x = side - 1 - x;
+                y = side - 1 - y;
+            }
+            std::mem::swap(&mut x, &mut y);
+        }
+        s /= 2;
+    }
+    d
+}
+
+// Square spiral walk from the center tile, skipping positions outside the grid
+fn spiral(nx: u32, ny: u32) -> Vec<(u32, u32)> {
+    let total = (nx * ny) as usize;
+    let mut coords = Vec::with_capacity(total);
+    if total == 0 {
+        return coords;
+    }
+    let (mut x, mut y) = (((nx - 1) / 2) as i64, ((ny - 1) / 2) as i64);
+    let directions = [(1, 0), (0, 1), (-1, 0), (0, -1)];
+    let mut direction = 0;
+    let mut run = 1;
+
+    let visit = |x: i64, y: i64, coords: &mut Vec<(u32, u32)>| {
+        if (0..nx as i64).contains(&x) && (0..ny as i64).contains(&y) {
+            coords.push((x as u32, y as u32));
+        }
+    };
+
+    visit(x, y, &mut coords);
+    while coords.len() < total {
+        // each run length is walked twice: right, down, then left, up, ...
+        for _ in 0..2 {
+            let (dx, dy) = directions[direction];
+            for _ in 0..run {
+                x += dx;
+                y += dy;
+                visit(x, y, &mut coords);
+            }
+            direction = (direction + 1) % 4;
+        }
+        run += 1;
+    }
+    coords
+}
@@ -1,16 +1,69 @@
-use crate::objects::Hittable;
+use std::collections::HashMap;
+
+use crate::objects::{Hit, Hittable, Light, SceneIds};
 use crate::prelude::*;
+use crate::render::stats::{count_path_ray, count_shadow_ray};
 
 pub struct World {
     pub backdrop: Color,
     pub geometry: Box<dyn Hittable>,
+    // Emitters of the geometry that are sampled directly
+    lights: Vec<Arc<dyn Light>>,
+    // object id -> index in lights
+    light_index: HashMap<u32, usize>,
 }
 
 impl World {
-    pub fn new(backdrop: Color, geometry: impl Hittable + 'static) -> Self {
+    pub fn new(backdrop: Color, mut geometry: impl Hittable + 'static) -> Self {
+        // ids first, the lights are copies that carry them
+        geometry.assign_ids(&mut SceneIds::default());
+        let mut lights = Vec::new();
+        geometry.collect_lights(&mut lights);
+        let light_index = lights
+            .iter()
+            .enumerate()
+            .map(|(index, light)| (light.object_id(), index))
+            .collect();
         Self {
             backdrop,
             geometry: Box::new(geometry),
+            lights,
+            light_index,
+        }
+    }
+
+    pub fn lights(&self) -> &[Arc<dyn Light>] {
+        &self.lights
+    }
+
+    // Closest hit along the ray
+    pub fn hit(&self, ray: &Ray, t_range: Interval, rng: &mut Rng) -> Option<Hit> {
+        count_path_ray();
+        self.geometry.hit(ray, t_range, rng)
+    }
+
+    // Whether anything is in the way, e.g. for shadow rays
+    pub fn occluded(&self, ray: &Ray, t_range: Interval, rng: &mut Rng) -> bool {
+        count_shadow_ray();
+        self.geometry.hit(ray, t_range, rng).is_some()
+    }
+
+    // One light picked uniformly, and the odds of picking it
+    pub fn pick_light(&self, rng: &mut Rng) -> Option<(&dyn Light, f64)> {
+        let count = self.lights.len();
+        if count == 0 {
+            return None;
+        }
+        let light = &self.lights[((rng.random_f64() * count as f64) as usize).min(count - 1)];
+        Some((light.as_ref(), 1.0 / count as f64))
+    }
+
+    // Solid angle density of light sampling from `origin` ending on the object
+    // 0 if the object is not one of the lights
+    pub fn light_pdf(&self, object_id: u32, origin: Point3, direction: Vec3) -> f64 {
+        match self.light_index.get(&object_id) {
+            Some(&index) => self.lights[index].pdf(origin, direction) / self.lights.len() as f64,
+            None => 0.0,
         }
     }
 }
@@ -0,0 +1,351 @@
+use std::io::{BufWriter, Write};
+
+use crate::render::Image;
+use crate::render::writers::deflate::zlib_compress;
+
+/*
+    Scanline OpenEXR writer
+
+    Writes linear R, G, B channels as a single-part scanline image.
+    Layout follows the "OpenEXR File Layout" document: magic + version,
+    header attributes, line offset table, then one chunk per block
+    of scanlines.
+*/
+
+const MAGIC: [u8; 4] = [0x76, 0x2F, 0x31, 0x01];
+const VERSION: [u8; 4] = [2, 0, 0, 0];
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ExrPixelType {
+    Half,
+    Float,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ExrCompression {
+    None,
+    // zlib over blocks of 16 scanlines
+    Zip,
+}
+
+impl ExrPixelType {
+    fn id(&self) -> i32 {
+        match self {
+            Self::Half => 1,
+            Self::Float => 2,
+        }
+    }
+
+    fn push(&self, bytes: &mut Vec<u8>, value: f64) {
+        match self {
+            Self::Half => bytes.extend(f32_to_half(value as f32).to_le_bytes()),
+            Self::Float => bytes.extend((value as f32).to_le_bytes()),
+        }
+    }
+}
+
+impl ExrCompression {
+    fn id(&self) -> u8 {
+        match self {
+            Self::None => 0,
+            Self::Zip => 3,
+        }
+    }
+
+    fn lines_per_block(&self) -> u32 {
+        match self {
+            Self::None => 1,
+            Self::Zip => 16,
+        }
+    }
+}
+
+pub fn write_exr(
+    image: &impl Image,
+    writer: impl Write,
+    pixel_type: ExrPixelType,
+    compression: ExrCompression,
+) -> std::io::Result<()> {
+    let resolution = image.resolution();
+    let mut writer = BufWriter::new(writer);
+
+    let header = header(resolution.width, resolution.height, pixel_type, compression);
+
+    // --- ENCODE BLOCKS ---
+    let lines_per_block = compression.lines_per_block();
+    let mut blocks = Vec::new();
+    for y0 in (0..resolution.height).step_by(lines_per_block as usize) {
+        let y1 = (y0 + lines_per_block).min(resolution.height);
+        let mut raw = Vec::new();
+        for j in y0..y1 {
+            // channels are stored in alphabetical order within each scanline
+            for channel in [2, 1, 0] {
+                for i in 0..resolution.width {
+                    let color = image.pixel(i, j);
+                    let value = match channel {
+                        0 => color.x,
+                        1 => color.y,
+                        _ => color.z,
+                    };
+                    pixel_type.push(&mut raw, value);
+                }
+            }
+        }
+
+        let data = match compression {
+            ExrCompression::None => raw,
+            ExrCompression::Zip => {
+                let compressed = zlib_compress(&zip_predict(&raw));
+                // readers treat a block as raw when it is not smaller
+                if compressed.len() < raw.len() {
+                    compressed
+                } else {
+                    raw
+                }
+            }
+        };
+        blocks.push((y0, data));
+    }
+
+    // --- WRITE FILE ---
+    writer.write_all(&MAGIC)?;
+    writer.write_all(&VERSION)?;
+    writer.write_all(&header)?;
+
+    let mut offset = (MAGIC.len() + VERSION.len() + header.len() + 8 * blocks.len()) as u64;
+    for (_, data) in &blocks {
+        writer.write_all(&offset.to_le_bytes())?;
+        offset += 8 + data.len() as u64;
+    }
+    for (y, data) in &blocks {
+        writer.write_all(&(*y as i32).to_le_bytes())?;
+        writer.write_all(&(data.len() as i32).to_le_bytes())?;
+        writer.write_all(data)?;
+    }
+
+    writer.flush()
+}
+
+fn header(
+    width: u32,
+    height: u32,
+    pixel_type: ExrPixelType,
+    compression: ExrCompression,
+) -> Vec<u8> {
+    let mut header = Vec::new();
+
+    let mut channels = Vec::new();
+    for name in ["B", "G", "R"] {
+        channels.extend(name.as_bytes());
+        channels.push(0);
+        channels.extend(pixel_type.id().to_le_bytes());
+        // pLinear + reserved
+        channels.extend([0, 0, 0, 0]);
+        // x and y sampling
+        channels.extend(1i32.to_le_bytes());
+        channels.extend(1i32.to_le_bytes());
+    }
+    channels.push(0);
+    attribute(&mut header, "channels", "chlist", &channels);
+
+    attribute(
+        &mut header,
+        "compression",
+        "compression",
+        &[compression.id()],
+    );
+
+    let mut window = Vec::new();
+    for value in [0, 0, width as i32 - 1, height as i32 - 1] {
+        window.extend(value.to_le_bytes());
+    }
+    attribute(&mut header, "dataWindow", "box2i", &window);
+    attribute(&mut header, "displayWindow", "box2i", &window);
+
+    // increasing y
+    attribute(&mut header, "lineOrder", "lineOrder", &[0]);
+    attribute(
+        &mut header,
+        "pixelAspectRatio",
+        "float",
+        &1.0f32.to_le_bytes(),
+    );
+
+    let mut center = Vec::new();
+    center.extend(0.0f32.to_le_bytes());
+    center.extend(0.0f32.to_le_bytes());
+    attribute(&mut header, "screenWindowCenter", "v2f", &center);
+    attribute(
+        &mut header,
+        "screenWindowWidth",
+        "float",
+        &1.0f32.to_le_bytes(),
+    );
+
+    header.push(0);
+    header
+}
+
+fn attribute(header: &mut Vec<u8>, name: &str, kind: &str, value: &[u8]) {
+    header.extend(name.as_bytes());
+    header.push(0);
+    header.extend(kind.as_bytes());
+    header.push(0);
+    header.extend((value.len() as i32).to_le_bytes());
+    header.extend(value);
+}
+
+// Byte interleaving and delta predictor applied before zlib in ZIP compression
+fn zip_predict(raw: &[u8]) -> Vec<u8> {
+    let half = raw.len().div_ceil(2);
+    let mut reordered = vec![0u8; raw.len()];
+    for (index, &byte) in raw.iter().enumerate() {
+        let target = if index % 2 == 0 {
+            index / 2
+        } else {
+            half + index / 2
+        };
+        reordered[target] = byte;
+    }
+
+    let mut previous = reordered.first().copied().unwrap_or(0);
+    for byte in reordered.iter_mut().skip(1) {
+        let current = *byte;
+        *byte = current.wrapping_sub(previous).wrapping_add(128);
+        previous = current;
+    }
+    reordered
+}
+
+// IEEE 754 binary16, round to nearest even
+fn f32_to_half(value: f32) -> u16 {
+    let bits = value.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xFF) as i32;
+    let mantissa = bits & 0x7F_FFFF;
+
+    if exponent == 0xFF {
+        // infinity or NaN
+        let nan = if mantissa != 0 { 0x200 } else { 0 };
+        return sign | 0x7C00 | nan;
+    }
+
+    let half_exponent = exponent - 127 + 15;
+    if half_exponent >= 0x1F {
+        // overflow to infinity
+        return sign | 0x7C00;
+    }
+
+    if half_exponent <= 0 {
+        // subnormal half or zero
+        if half_exponent < -10 {
+            return sign;
+        }
+        let mantissa = mantissa | 0x80_0000;
+        let shift = (14 - half_exponent) as u32;
+        let mut half_mantissa = mantissa >> shift;
+        let remainder = mantissa & ((1 << shift) - 1);
+        let halfway = 1 << (shift - 1);
+        if remainder > halfway || (remainder == halfway && half_mantissa & 1 != 0) {
+            half_mantissa += 1;
+        }
+        return sign | half_mantissa as u16;
+    }
+
+    let mut half = ((half_exponent as u32) << 10) | (mantissa >> 13);
+    let remainder = mantissa & 0x1FFF;
+    if remainder > 0x1000 || (remainder == 0x1000 && half & 1 != 0) {
+        // may carry into the exponent, which is the correct rounding
+        half += 1;
+    }
+    sign | half as u16
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn half_to_f32(half: u16) -> f32 {
+        let sign = if half & 0x8000 != 0 { -1.0 } else { 1.0 };
+        let exponent = ((half >> 10) & 0x1F) as i32;
+        let mantissa = (half & 0x3FF) as f32;
+        match exponent {
+            0 => sign * mantissa * 2f32.powi(-24),
+            0x1F if mantissa == 0.0 => sign * f32::INFINITY,
+            0x1F => f32::NAN,
+            _ => sign * (1.0 + mantissa / 1024.0) * 2f32.powi(exponent - 15),
+        }
+    }
+
+    // Inverse of zip_predict, as an EXR reader does it
+    fn zip_unpredict(predicted: &[u8]) -> Vec<u8> {
+        let mut reordered = predicted.to_vec();
+        for index in 1..reordered.len() {
+            reordered[index] = reordered[index - 1]
+                .wrapping_add(reordered[index])
+                .wrapping_sub(128);
+        }
+        let half = reordered.len().div_ceil(2);
+        (0..reordered.len())
+            .map(|index| {
+                if index % 2 == 0 {
+                    reordered[index / 2]
+                } else {
+                    reordered[half + index / 2]
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn half_known_values() {
+        assert_eq!(f32_to_half(0.0), 0x0000);
+        assert_eq!(f32_to_half(-0.0), 0x8000);
+        assert_eq!(f32_to_half(1.0), 0x3C00);
+        assert_eq!(f32_to_half(-2.0), 0xC000);
+        assert_eq!(f32_to_half(0.1), 0x2E66);
+        assert_eq!(f32_to_half(65504.0), 0x7BFF);
+        // smallest normal and subnormal
+        assert_eq!(f32_to_half(2f32.powi(-14)), 0x0400);
+        assert_eq!(f32_to_half(2f32.powi(-24)), 0x0001);
+        assert_eq!(f32_to_half(2f32.powi(-26)), 0x0000);
+        assert_eq!(f32_to_half(f32::INFINITY), 0x7C00);
+        assert_eq!(f32_to_half(f32::NEG_INFINITY), 0xFC00);
+        assert_eq!(f32_to_half(f32::NAN) & 0x7C00, 0x7C00);
+        assert_ne!(f32_to_half(f32::NAN) & 0x3FF, 0);
+    }
+
+    #[test]
+    fn half_rounds_to_nearest_even() {
+        let ulp = 2f32.powi(-10);
+        assert_eq!(f32_to_half(1.0 + ulp / 2.0), 0x3C00);
+        assert_eq!(f32_to_half(1.0 + 3.0 * ulp / 2.0), 0x3C02);
+        assert_eq!(f32_to_half(1.0 + ulp * 0.51), 0x3C01);
+        // halfway past the largest finite value goes to infinity
+        assert_eq!(f32_to_half(65520.0), 0x7C00);
+        assert_eq!(f32_to_half(65519.0), 0x7BFF);
+        // carry from a subnormal into the smallest normal
+        assert_eq!(f32_to_half(2f32.powi(-14) - 2f32.powi(-25)), 0x0400);
+    }
+
+    #[test]
+    fn half_round_trips_every_finite_value() {
+        for half in 0..=u16::MAX {
+            if half & 0x7C00 == 0x7C00 {
+                continue;
+            }
+            assert_eq!(f32_to_half(half_to_f32(half)), half, "{half:#06x}");
+        }
+    }
+
+    #[test]
+    fn zip_predictor_round_trip() {
+        for length in [0, 1, 2, 7, 64] {
+            let raw: Vec<u8> = (0..length).map(|k| (k * 37 % 251) as u8).collect();
+            assert_eq!(zip_unpredict(&zip_predict(&raw)), raw);
+        }
+        // smooth data turns into values around 128
+        let predicted = zip_predict(&[10, 0, 11, 0, 12, 0, 13, 0]);
+        assert_eq!(predicted, [10, 129, 129, 129, 115, 128, 128, 128]);
+    }
+}
@@ -0,0 +1,78 @@
+mod deflate;
+mod exr;
+mod pfm;
+mod png;
+mod ppm;
+
+pub use exr::{ExrCompression, ExrPixelType, write_exr};
+pub use pfm::write_pfm;
+
+use std::io::Write;
+
+use png::write_png;
+use ppm::{write_binary_ppm, write_ppm};
+
+use crate::render::{DisplayTransform, Image};
+
+// LDR formats store display-encoded colors, see DisplayTransform
+#[derive(Clone, Copy)]
+pub enum ImageFormat {
+    // Plain-text P3, readable but large
+    AsciiPpm,
+    // Binary P6
+    BinaryPpm,
+    Png,
+    // Linear HDR formats, written without clamping or gamma
+    Pfm,
+    Exr {
+        pixel_type: ExrPixelType,
+        compression: ExrCompression,
+    },
+}
+
+impl ImageFormat {
+    pub const EXR: ImageFormat = ImageFormat::Exr {
+        pixel_type: ExrPixelType::Half,
+        compression: ExrCompression::Zip,
+    };
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::AsciiPpm | Self::BinaryPpm => "ppm",
+            Self::Png => "png",
+            Self::Pfm => "pfm",
+            Self::Exr { .. } => "exr",
+        }
+    }
+
+    pub fn is_hdr(&self) -> bool {
+        matches!(self, Self::Pfm | Self::Exr { .. })
+    }
+
+    // Writes a linear image, LDR formats get the default display transform
+    pub fn write(&self, image: &impl Image, writer: impl Write) -> std::io::Result<()> {
+        if self.is_hdr() {
+            self.write_encoded(image, writer)
+        } else {
+            self.write_encoded(&DisplayTransform::default().view(image), writer)
+        }
+    }
+
+    // LDR formats take the colors as already display-encoded, HDR formats as linear
+    pub(crate) fn write_encoded(
+        &self,
+        image: &impl Image,
+        writer: impl Write,
+    ) -> std::io::Result<()> {
+        match *self {
+            Self::AsciiPpm => write_ppm(image, writer),
+            Self::BinaryPpm => write_binary_ppm(image, writer),
+            Self::Png => write_png(image, writer),
+            Self::Pfm => write_pfm(image, writer),
+            Self::Exr {
+                pixel_type,
+                compression,
+            } => write_exr(image, writer, pixel_type, compression),
+        }
+    }
+}
@@ -0,0 +1,26 @@
+use std::io::{BufWriter, Write};
+
+use crate::render::Image;
+
+// Portable Float Map, linear little-endian f32 RGB stored bottom row first
+pub fn write_pfm(image: &impl Image, writer: impl Write) -> std::io::Result<()> {
+    let resolution = image.resolution();
+    let mut writer = BufWriter::new(writer);
+    // negative scale marks little-endian data
+    write!(
+        writer,
+        "PF\n{} {}\n-1.0\n",
+        resolution.width, resolution.height
+    )?;
+
+    for j in (0..resolution.height).rev() {
+        for i in 0..resolution.width {
+            let color = image.pixel(i, j);
+            for value in [color.x, color.y, color.z] {
+                writer.write_all(&(value as f32).to_le_bytes())?;
+            }
+        }
+    }
+
+    writer.flush()
+}
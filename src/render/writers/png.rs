@@ -0,0 +1,167 @@
+use std::io::{BufWriter, Write};
+
+use crate::prelude::*;
+use crate::render::Image;
+use crate::render::writers::deflate::zlib_compress;
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n'];
+const CRC_TABLE: [u32; 256] = crc_table();
+
+// 8-bit RGB PNG
+pub fn write_png(image: &impl Image, writer: impl Write) -> std::io::Result<()> {
+    let resolution = image.resolution();
+    let mut writer = BufWriter::new(writer);
+    writer.write_all(&SIGNATURE)?;
+
+    let mut header = Vec::with_capacity(13);
+    header.extend(resolution.width.to_be_bytes());
+    header.extend(resolution.height.to_be_bytes());
+    // bit depth 8, color type 2 (RGB), deflate, adaptive filtering, no interlace
+    header.extend([8, 2, 0, 0, 0]);
+    write_chunk(&mut writer, b"IHDR", &header)?;
+
+    let stride = 3 * resolution.width as usize;
+    let mut scanlines = Vec::with_capacity((stride + 1) * resolution.height as usize);
+    let mut previous = vec![0u8; stride];
+    let mut current = Vec::with_capacity(stride);
+    for j in 0..resolution.height {
+        current.clear();
+        for i in 0..resolution.width {
+            current.extend(color_to_bytes(image.pixel(i, j)));
+        }
+        filter_row(&current, &previous, &mut scanlines);
+        std::mem::swap(&mut current, &mut previous);
+    }
+    write_chunk(&mut writer, b"IDAT", &zlib_compress(&scanlines))?;
+
+    write_chunk(&mut writer, b"IEND", &[])?;
+    writer.flush()
+}
+
+fn write_chunk(writer: &mut impl Write, kind: &[u8; 4], data: &[u8]) -> std::io::Result<()> {
+    writer.write_all(&(data.len() as u32).to_be_bytes())?;
+    writer.write_all(kind)?;
+    writer.write_all(data)?;
+    let crc = crc32_update(crc32_update(0xFFFF_FFFF, kind), data) ^ 0xFFFF_FFFF;
+    writer.write_all(&crc.to_be_bytes())
+}
+
+/*
+    Tries every PNG filter on the row and keeps the one with the
+    smallest sum of absolute (signed) residuals, the usual heuristic
+*/
+fn filter_row(row: &[u8], previous: &[u8], out: &mut Vec<u8>) {
+    const BPP: usize = 3;
+    let mut best: Option<(u64, u8, Vec<u8>)> = None;
+
+    for filter in 0..5u8 {
+        let filtered: Vec<u8> = (0..row.len())
+            .map(|x| {
+                let a = if x >= BPP { row[x - BPP] } else { 0 };
+                let b = previous[x];
+                let c = if x >= BPP { previous[x - BPP] } else { 0 };
+                let predictor = match filter {
+                    0 => 0,
+                    1 => a,
+                    2 => b,
+                    3 => ((a as u16 + b as u16) / 2) as u8,
+                    _ => paeth(a, b, c),
+                };
+                row[x].wrapping_sub(predictor)
+            })
+            .collect();
+
+        let cost = filtered
+            .iter()
+            .map(|&v| (v as i8).unsigned_abs() as u64)
+            .sum();
+        if best
+            .as_ref()
+            .is_none_or(|(best_cost, _, _)| cost < *best_cost)
+        {
+            best = Some((cost, filter, filtered));
+        }
+    }
+
+    let (_, filter, filtered) = best.unwrap();
+    out.push(filter);
+    out.extend(filtered);
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let pa = (p - a as i16).abs();
+    let pb = (p - b as i16).abs();
+    let pc = (p - c as i16).abs();
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+fn crc32_update(mut crc: u32, data: &[u8]) -> u32 {
+    for &byte in data {
+        crc = CRC_TABLE[((crc ^ byte as u32) & 0xFF) as usize] ^ (crc >> 8);
+    }
+    crc
+}
+
+const fn crc_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut n = 0;
+    while n < 256 {
+        let mut c = n as u32;
+        let mut k = 0;
+        while k < 8 {
+            c = if c & 1 != 0 {
+                0xEDB8_8320 ^ (c >> 1)
+            } else {
+                c >> 1
+            };
+            k += 1;
+        }
+        table[n] = c;
+        n += 1;
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn crc32(data: &[u8]) -> u32 {
+        crc32_update(0xFFFF_FFFF, data) ^ 0xFFFF_FFFF
+    }
+
+    #[test]
+    fn crc32_known_values() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(
+            crc32(b"The quick brown fox jumps over the lazy dog"),
+            0x414F_A339
+        );
+    }
+
+    #[test]
+    fn iend_chunk() {
+        let mut out = Vec::new();
+        write_chunk(&mut out, b"IEND", &[]).unwrap();
+        assert_eq!(
+            out,
+            [0, 0, 0, 0, b'I', b'E', b'N', b'D', 0xAE, 0x42, 0x60, 0x82]
+        );
+    }
+
+    #[test]
+    fn paeth_picks_closest() {
+        assert_eq!(paeth(10, 20, 10), 20);
+        assert_eq!(paeth(20, 10, 10), 20);
+        assert_eq!(paeth(10, 10, 10), 10);
+        assert_eq!(paeth(0, 255, 255), 0);
+    }
+}
@@ -0,0 +1,40 @@
+use std::io::{BufWriter, Write};
+
+use crate::prelude::*;
+use crate::render::Image;
+
+// Plain-text PPM (P3), one pixel per line
+pub fn write_ppm(image: &impl Image, writer: impl Write) -> std::io::Result<()> {
+    let resolution = image.resolution();
+    let mut writer = BufWriter::new(writer);
+    writeln!(writer, "P3")?;
+    writeln!(writer, "{} {}", resolution.width, resolution.height)?;
+    writeln!(writer, "255")?;
+
+    for j in 0..resolution.height {
+        for i in 0..resolution.width {
+            write_color(&mut writer, image.pixel(i, j))?;
+        }
+    }
+
+    writer.flush()
+}
+
+// Binary PPM (P6), three bytes per pixel
+pub fn write_binary_ppm(image: &impl Image, writer: impl Write) -> std::io::Result<()> {
+    let resolution = image.resolution();
+    let mut writer = BufWriter::new(writer);
+    write!(
+        writer,
+        "P6\n{} {}\n255\n",
+        resolution.width, resolution.height
+    )?;
+
+    for j in 0..resolution.height {
+        for i in 0..resolution.width {
+            writer.write_all(&color_to_bytes(image.pixel(i, j)))?;
+        }
+    }
+
+    writer.flush()
+}